@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use csgrs::csg::CSG;
+use csgrs::float_types::Real;
+use eframe::egui::{Event, Pos2, TouchDeviceId, TouchId, TouchPhase};
+use glam::{Quat, Vec3};
+use nalgebra::Point3;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh::to_vec3;
+
+/// Eye distance, in model units, at which `zoom` 1 shows one model unit as
+/// a quarter of the canvas; `zoom` is relative to the view from here.
+pub const EYE_DIST: f32 = 4.0;
+
+/// Where the eye is, how much it magnifies and how it projects.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Camera {
+    /// Model-to-view rotation.
+    pub rotation: Quat,
+    pub zoom: f32,
+    /// Model-space point the eye looks at; panning moves it.
+    pub target: Vec3,
+    /// From the eye to `target`, in model units.
+    pub distance: f32,
+    #[serde(default)]
+    pub projection: Projection,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            rotation: Quat::IDENTITY,
+            zoom: 1.0,
+            target: Vec3::ZERO,
+            distance: EYE_DIST,
+            projection: Projection::Perspective,
+        }
+    }
+}
+
+impl Camera {
+    /// Refuse a view read from a file or link that cannot be drawn: values
+    /// that are not finite numbers, or no zoom, distance or rotation.
+    pub fn sanitize(&mut self) -> Result<(), String> {
+        let finite = self.rotation.is_finite()
+            && self.target.is_finite()
+            && self.zoom.is_finite()
+            && self.distance.is_finite();
+        if !finite || self.zoom <= 0.0 || self.distance <= 0.0 || self.rotation.length() == 0.0 {
+            return Err("the view has a zoom, distance or rotation that cannot be drawn".into());
+        }
+        self.rotation = self.rotation.normalize();
+        Ok(())
+    }
+}
+
+/// How the view volume maps to the canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Projection {
+    /// Nearer parts look larger.
+    #[default]
+    Perspective,
+    /// Parallel rays: sizes read the same at every depth, as on a drawing.
+    Orthographic,
+}
+
+impl Projection {
+    pub const ALL: [Self; 2] = [Self::Perspective, Self::Orthographic];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Perspective => "Perspective",
+            Self::Orthographic => "Orthographic",
+        }
+    }
+}
+
+/// Standard viewing directions, for a model with `+Z` up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NamedView {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Isometric,
+}
+
+impl NamedView {
+    pub const ALL: [Self; 7] = [
+        Self::Front,
+        Self::Back,
+        Self::Left,
+        Self::Right,
+        Self::Top,
+        Self::Bottom,
+        Self::Isometric,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Front => "Front",
+            Self::Back => "Back",
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::Top => "Top",
+            Self::Bottom => "Bottom",
+            Self::Isometric => "Isometric",
+        }
+    }
+
+    /// Model-to-view rotation: the eye looks down view `-Z` with view `+Y`
+    /// up.
+    pub fn rotation(self) -> Quat {
+        // seen from -Y: model Z up, X to the right
+        let front = Quat::from_rotation_x(-FRAC_PI_2);
+        match self {
+            Self::Front => front,
+            Self::Back => Quat::from_rotation_y(PI) * front,
+            Self::Left => Quat::from_rotation_y(FRAC_PI_2) * front,
+            Self::Right => Quat::from_rotation_y(-FRAC_PI_2) * front,
+            Self::Top => Quat::IDENTITY,
+            Self::Bottom => Quat::from_rotation_x(PI),
+            // from the front-right-top corner, all three axes equally foreshortened
+            Self::Isometric => {
+                let tilt = (1.0 / 2.0f32.sqrt()).atan();
+                Quat::from_rotation_x(tilt) * Quat::from_rotation_y(-FRAC_PI_4) * front
+            }
+        }
+    }
+}
+
+/// How a drag turns the model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrbitMode {
+    /// Yaw about the model's `+Z`, which stays upright on screen.
+    #[default]
+    TurntableZ,
+    /// Yaw about the model's `+Y`, which stays upright on screen.
+    TurntableY,
+    /// Roll the model like a ball under the pointer; any orientation goes.
+    Arcball,
+}
+
+impl OrbitMode {
+    pub const ALL: [Self; 3] = [Self::TurntableZ, Self::TurntableY, Self::Arcball];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::TurntableZ => "Turntable (Z up)",
+            Self::TurntableY => "Turntable (Y up)",
+            Self::Arcball => "Arcball",
+        }
+    }
+
+    /// `rotation` after the pointer moved from `from` to `to`. The arcball
+    /// is a sphere of `radius` pixels about `center`.
+    pub fn drag(self, rotation: Quat, from: Pos2, to: Pos2, center: Pos2, radius: f32) -> Quat {
+        const RADIANS_PER_PIXEL: f32 = 0.01;
+        let delta = to - from;
+        match self {
+            Self::TurntableZ | Self::TurntableY => {
+                let (yaw, pitch) = self.yaw_pitch(rotation);
+                let yaw = yaw + delta.x * RADIANS_PER_PIXEL;
+                let pitch = (pitch + delta.y * RADIANS_PER_PIXEL).clamp(-FRAC_PI_2, FRAC_PI_2);
+                self.turntable(yaw, pitch)
+            }
+            Self::Arcball => {
+                let on_ball = |p: Pos2| {
+                    let v = (p - center) / radius.max(1.0);
+                    let (x, y) = (v.x, -v.y);
+                    let d2 = x * x + y * y;
+                    // sphere near the middle, hyperbolic sheet outside so the
+                    // rim keeps turning instead of snapping
+                    let z = if d2 <= 0.5 {
+                        (1.0 - d2).sqrt()
+                    } else {
+                        0.5 / d2.sqrt()
+                    };
+                    Vec3::new(x, y, z).normalize()
+                };
+                Quat::from_rotation_arc(on_ball(from), on_ball(to)) * rotation
+            }
+        }
+    }
+
+    /// `rotation` after a two-finger twist of `angle` radians, clockwise on
+    /// screen. The arcball rolls about the line of sight; a turntable
+    /// yaws instead so its up axis stays upright.
+    pub fn twist(self, rotation: Quat, angle: f32) -> Quat {
+        match self {
+            Self::TurntableZ | Self::TurntableY => {
+                let (yaw, pitch) = self.yaw_pitch(rotation);
+                self.turntable(yaw + angle, pitch)
+            }
+            // view +z points out of the screen
+            Self::Arcball => Quat::from_rotation_z(-angle) * rotation,
+        }
+    }
+
+    /// Upright rotation with the eye `yaw` around and `pitch` above the
+    /// horizon; `(0, 0)` looks at the model from its `+Z` (Y up) or `-Y`
+    /// (Z up) side.
+    fn turntable(self, yaw: f32, pitch: f32) -> Quat {
+        match self {
+            Self::TurntableY => Quat::from_rotation_x(pitch) * Quat::from_rotation_y(yaw),
+            _ => Quat::from_rotation_x(pitch - FRAC_PI_2) * Quat::from_rotation_z(yaw),
+        }
+    }
+
+    /// Inverse of `turntable`, dropping any roll.
+    fn yaw_pitch(self, rotation: Quat) -> (f32, f32) {
+        let inverse = rotation.inverse();
+        // towards the eye and to the right of the screen, in model space
+        let eye = inverse * Vec3::Z;
+        let right = inverse * Vec3::X;
+        match self {
+            Self::TurntableY => (right.z.atan2(right.x), eye.y.clamp(-1.0, 1.0).asin()),
+            _ => ((-right.y).atan2(right.x), eye.z.clamp(-1.0, 1.0).asin()),
+        }
+    }
+}
+
+/// Eased rotation from one view to another.
+#[derive(Clone, Copy, Debug)]
+pub struct ViewTransition {
+    from: Quat,
+    to: Quat,
+    /// `egui` input time at which the transition began.
+    start: f64,
+}
+
+impl ViewTransition {
+    /// Seconds from start to end.
+    const DURATION: f64 = 0.35;
+
+    pub fn new(from: Quat, to: Quat, now: f64) -> Self {
+        Self {
+            from,
+            to,
+            start: now,
+        }
+    }
+
+    /// Rotation at `now` and whether the transition is still running.
+    pub fn at(&self, now: f64) -> (Quat, bool) {
+        let t = ((now - self.start) / Self::DURATION).clamp(0.0, 1.0) as f32;
+        let eased = t * t * (3.0 - 2.0 * t);
+        (self.from.slerp(self.to, eased), t < 1.0)
+    }
+}
+
+/// Centre and radius of a sphere around every vertex of `csg`; `None` for
+/// an empty model.
+pub fn bounding_sphere(csg: &CSG<()>) -> Option<(Vec3, f32)> {
+    points_sphere(csg.polygons.iter().flat_map(|p| &p.vertices).map(|v| v.pos))
+}
+
+/// Centre and radius of a sphere around `points`; `None` when there are
+/// none.
+///
+/// Centred on the bounding box, which is close enough to the smallest sphere
+/// for framing and never far off for boxy parts.
+pub fn points_sphere(points: impl Iterator<Item = Point3<Real>> + Clone) -> Option<(Vec3, f32)> {
+    let (min, max) = points
+        .clone()
+        .map(|p| (p, p))
+        .reduce(|(lo, hi), (p, _)| (lo.inf(&p), hi.sup(&p)))?;
+    let center = nalgebra::center(&min, &max);
+    let radius = points.map(|p| (p - center).norm()).fold(0.0, Real::max);
+    Some((to_vec3(&center.coords), radius as f32))
+}
+
+/// Fingers currently on the screen.
+///
+/// egui reports pinch, twist and drag of a multi-touch gesture but not where
+/// it happens, so positions are tracked here to zoom about the fingers.
+#[derive(Default)]
+pub struct Touches {
+    points: BTreeMap<(TouchDeviceId, TouchId), Pos2>,
+}
+
+impl Touches {
+    pub fn update(&mut self, events: &[Event]) {
+        for event in events {
+            if let Event::Touch {
+                device_id,
+                id,
+                phase,
+                pos,
+                ..
+            } = event
+            {
+                match phase {
+                    TouchPhase::Start | TouchPhase::Move => {
+                        self.points.insert((*device_id, *id), *pos);
+                    }
+                    TouchPhase::End | TouchPhase::Cancel => {
+                        self.points.remove(&(*device_id, *id));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Centre of the fingers, while there is more than one.
+    pub fn center(&self) -> Option<Pos2> {
+        let n = self.points.len();
+        (n > 1).then(|| {
+            let sum = self
+                .points
+                .values()
+                .fold(Pos2::ZERO, |acc, p| acc + p.to_vec2());
+            Pos2::new(sum.x / n as f32, sum.y / n as f32)
+        })
+    }
+}
@@ -0,0 +1,254 @@
+use eframe::egui::{self, Color32, Pos2, Rect};
+use glam::{Mat4, Quat, Vec3};
+
+use crate::edges::EdgeSet;
+use crate::mesh::TriMesh;
+
+/// Maps model points to the canvas for one frame of CPU drawing or overlay
+/// painting.
+pub struct Projector {
+    view: Mat4,
+    projection: Mat4,
+    rect: Rect,
+}
+
+impl Projector {
+    pub fn new(view: Mat4, projection: Mat4, rect: Rect) -> Self {
+        Self {
+            view,
+            projection,
+            rect,
+        }
+    }
+
+    /// Screen position and distance in front of the eye.
+    pub fn project(&self, p: Vec3) -> (Pos2, f32) {
+        let v = self.view.transform_point3(p);
+        let clip = self.projection * v.extend(1.0);
+        let ndc = clip.truncate() / clip.w;
+        let half = self.rect.size() * 0.5;
+        (
+            self.rect.center() + egui::vec2(ndc.x * half.x, -ndc.y * half.y),
+            -v.z,
+        )
+    }
+
+    /// Model-space ray through a canvas position: its origin on the near
+    /// plane and unit direction into the scene.
+    pub fn ray(&self, pos: Pos2) -> (Vec3, Vec3) {
+        let half = self.rect.size() * 0.5;
+        let offset = pos - self.rect.center();
+        let (x, y) = (offset.x / half.x, -offset.y / half.y);
+        let inverse = (self.projection * self.view).inverse();
+        let near = inverse.project_point3(Vec3::new(x, y, -1.0));
+        let far = inverse.project_point3(Vec3::new(x, y, 1.0));
+        (near, (far - near).normalize_or_zero())
+    }
+
+    /// Whether rays are parallel rather than meeting at the eye.
+    pub fn orthographic(&self) -> bool {
+        self.projection.w_axis.w != 0.0
+    }
+
+    /// Model-space position of the eye.
+    pub fn eye(&self) -> Vec3 {
+        self.view.inverse().transform_point3(Vec3::ZERO)
+    }
+}
+
+/// Lit vertex colours: a headlight with a Phong specular term.
+pub fn shade(mesh: &TriMesh, rotation: Quat, base: Vec3) -> Vec<Color32> {
+    const AMBIENT: f32 = 0.2;
+    const SHININESS: f32 = 32.0;
+    // view space: camera looks down -z, light sits slightly above and left of
+    // it (keep in sync with the fragment shader in `gpu.rs`)
+    let light = Vec3::new(-0.3, 0.4, 1.0).normalize();
+    let view = Vec3::Z;
+
+    mesh.normals
+        .iter()
+        .map(|&n| {
+            let mut n = rotation * n;
+            // light both sides so open shells and inverted faces stay readable
+            if n.z < 0.0 {
+                n = -n;
+            }
+            let diffuse = n.dot(light).max(0.0);
+            let reflected = 2.0 * n.dot(light) * n - light;
+            let specular = reflected.dot(view).max(0.0).powf(SHININESS) * 0.35;
+            let c = base * (AMBIENT + (1.0 - AMBIENT) * diffuse) + Vec3::splat(specular);
+            let c = c.clamp(Vec3::ZERO, Vec3::ONE) * 255.0;
+            Color32::from_rgb(c.x as u8, c.y as u8, c.z as u8)
+        })
+        .collect()
+}
+
+/// Gouraud-shaded triangles of `mesh`.
+///
+/// egui's painter has no depth test, so triangles are depth-sorted per frame
+/// and emitted back to front; the later ones overwrite what they occlude.
+pub fn sorted_triangles(
+    mesh: &TriMesh,
+    projected: &[(Pos2, f32)],
+    colors: &[Color32],
+) -> egui::Mesh {
+    let mut order: Vec<([u32; 3], f32)> = mesh
+        .triangles()
+        .map(|t| {
+            let depth = t.iter().map(|&i| projected[i as usize].1).sum::<f32>();
+            (t, depth)
+        })
+        .collect();
+    order.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut out = egui::Mesh::default();
+    out.vertices.reserve(order.len() * 3);
+    out.indices.reserve(order.len() * 3);
+    for (tri, _) in order {
+        for i in tri {
+            out.indices.push(out.vertices.len() as u32);
+            out.vertices.push(egui::epaint::Vertex {
+                pos: projected[i as usize].0,
+                uv: egui::epaint::WHITE_UV,
+                color: colors[i as usize],
+            });
+        }
+    }
+    out
+}
+
+/// Screen-space segments of `edges`, split into the runs that are in front
+/// of every face and the runs some face of `mesh` hides.
+#[derive(Default)]
+pub struct EdgeVisibility {
+    pub visible: Vec<[Pos2; 2]>,
+    pub hidden: Vec<[Pos2; 2]>,
+}
+
+/// Classify `edges` against the projected triangles of `mesh`.
+///
+/// Each edge is sampled every few pixels; a sample is hidden when a
+/// triangle covers it and is nearer by more than a small relative margin,
+/// which keeps edges from being occluded by the faces they border.
+pub fn edge_visibility(
+    mesh: &TriMesh,
+    projected: &[(Pos2, f32)],
+    edges: &EdgeSet,
+    projector: &Projector,
+    rect: Rect,
+) -> EdgeVisibility {
+    const GRID: usize = 64;
+    const PIXELS_PER_SAMPLE: f32 = 4.0;
+    const MAX_SAMPLES: usize = 64;
+    const DEPTH_MARGIN: f32 = 1e-3;
+
+    // bucket triangles by the grid cells their screen bounds overlap
+    let cell_size = egui::vec2(rect.width() / GRID as f32, rect.height() / GRID as f32);
+    let cell_of = |p: Pos2| {
+        let c = (p - rect.min) / cell_size;
+        (
+            (c.x.floor().max(0.0) as usize).min(GRID - 1),
+            (c.y.floor().max(0.0) as usize).min(GRID - 1),
+        )
+    };
+    // depth is affine in screen space under a parallel projection, 1/depth
+    // under perspective
+    let orthographic = projector.orthographic();
+    let mut grid: Vec<Vec<u32>> = vec![Vec::new(); GRID * GRID];
+    for (t, tri) in mesh.triangles().enumerate() {
+        let pts = tri.map(|i| projected[i as usize]);
+        if pts.iter().any(|(_, d)| *d <= 0.0) {
+            continue;
+        }
+        let bounds = Rect::from_points(&pts.map(|(p, _)| p));
+        if !bounds.intersects(rect) {
+            continue;
+        }
+        let (x0, y0) = cell_of(bounds.min);
+        let (x1, y1) = cell_of(bounds.max);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                grid[y * GRID + x].push(t as u32);
+            }
+        }
+    }
+
+    let hidden_at = |p: Pos2, depth: f32| {
+        let (x, y) = cell_of(p);
+        grid[y * GRID + x].iter().any(|&t| {
+            let t = t as usize * 3;
+            let [a, b, c] = [0, 1, 2].map(|k| projected[mesh.indices[t + k] as usize]);
+            let Some([wa, wb, wc]) = barycentric(p, a.0, b.0, c.0) else {
+                return false;
+            };
+            let face = if orthographic {
+                wa * a.1 + wb * b.1 + wc * c.1
+            } else {
+                let inv = wa / a.1 + wb / b.1 + wc / c.1;
+                if inv <= 0.0 {
+                    return false;
+                }
+                1.0 / inv
+            };
+            face < depth * (1.0 - DEPTH_MARGIN)
+        })
+    };
+
+    let mut out = EdgeVisibility::default();
+    for (a, b) in edges.segments() {
+        let (pa, da) = projector.project(a);
+        let (pb, db) = projector.project(b);
+        if da <= 0.0 || db <= 0.0 {
+            continue;
+        }
+        let n = ((pa.distance(pb) / PIXELS_PER_SAMPLE) as usize).clamp(1, MAX_SAMPLES);
+        let point = |t: f32| pa + (pb - pa) * t;
+
+        // walk the sub-segments, merging neighbours with the same visibility
+        let mut run_start = 0.0;
+        let mut run_hidden = None;
+        for i in 0..n {
+            let t = (i as f32 + 0.5) / n as f32;
+            let depth = if orthographic {
+                da + (db - da) * t
+            } else {
+                1.0 / ((1.0 - t) / da + t / db)
+            };
+            let hidden = hidden_at(point(t), depth);
+            if run_hidden.is_some_and(|h| h != hidden) {
+                let t0 = i as f32 / n as f32;
+                let seg = [point(run_start), point(t0)];
+                if hidden {
+                    &mut out.visible
+                } else {
+                    &mut out.hidden
+                }
+                .push(seg);
+                run_start = t0;
+            }
+            run_hidden = Some(hidden);
+        }
+        if let Some(hidden) = run_hidden {
+            let seg = [point(run_start), pb];
+            if hidden {
+                &mut out.hidden
+            } else {
+                &mut out.visible
+            }
+            .push(seg);
+        }
+    }
+    out
+}
+
+/// Barycentric weights of `p` in the screen triangle `abc`, if inside.
+fn barycentric(p: Pos2, a: Pos2, b: Pos2, c: Pos2) -> Option<[f32; 3]> {
+    let area = (b - a).x * (c - a).y - (b - a).y * (c - a).x;
+    if area.abs() < f32::EPSILON {
+        return None;
+    }
+    let wa = ((b - p).x * (c - p).y - (b - p).y * (c - p).x) / area;
+    let wb = ((c - p).x * (a - p).y - (c - p).y * (a - p).x) / area;
+    let wc = 1.0 - wa - wb;
+    (wa >= 0.0 && wb >= 0.0 && wc >= 0.0).then_some([wa, wb, wc])
+}
@@ -0,0 +1,70 @@
+use glam::Vec3;
+
+use crate::mesh::to_vec3;
+use crate::weld::WeldedMesh;
+
+/// Why an edge survived extraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Used by a single polygon: an open border of the shell.
+    Boundary,
+    /// Shared by more than two polygons.
+    NonManifold,
+    /// Shared by two polygons meeting at more than the crease angle.
+    Crease,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FeatureEdge {
+    /// Endpoints as indices into [`EdgeSet::vertices`].
+    pub v: [u32; 2],
+    pub kind: EdgeKind,
+}
+
+/// Outline edges as pairs of indices into shared endpoints; the renderers
+/// draw them from [`EdgeSet::segments`].
+#[derive(Clone, Debug, Default)]
+pub struct EdgeSet {
+    pub vertices: Vec<Vec3>,
+    pub edges: Vec<FeatureEdge>,
+}
+
+impl EdgeSet {
+    /// Endpoint positions of every edge.
+    pub fn segments(&self) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+        self.edges.iter().map(|e| {
+            (
+                self.vertices[e.v[0] as usize],
+                self.vertices[e.v[1] as usize],
+            )
+        })
+    }
+}
+
+/// Collect the edges that outline `mesh` the way a CAD view would.
+///
+/// Edges between polygons whose normals differ by no more than
+/// `crease_angle_deg` are dropped, which hides the diagonals that booleans
+/// and triangulation leave across flat faces.
+pub fn feature_edges(mesh: &WeldedMesh, crease_angle_deg: f32) -> EdgeSet {
+    let cos_crease = crease_angle_deg.to_radians().cos();
+    let normal = |p: usize| to_vec3(&mesh.normals[p]);
+    let edges = mesh
+        .edges()
+        .into_iter()
+        .filter_map(|(v, polys)| {
+            let kind = match polys.as_slice() {
+                [_] => EdgeKind::Boundary,
+                [p0, p1] if normal(*p0).dot(normal(*p1)) < cos_crease => EdgeKind::Crease,
+                [_, _] => return None,
+                _ => EdgeKind::NonManifold,
+            };
+            Some(FeatureEdge { v, kind })
+        })
+        .collect();
+
+    EdgeSet {
+        vertices: mesh.vertices_f32(),
+        edges,
+    }
+}
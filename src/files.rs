@@ -0,0 +1,127 @@
+use eframe::egui;
+
+/// Contents of a file the user handed to the app.
+pub struct LoadedFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Files dropped onto the window this frame.
+///
+/// On the web the browser hands over the bytes directly; natively only the
+/// path is known and the file is read here.
+pub fn dropped_files(ctx: &egui::Context) -> Vec<Result<LoadedFile, String>> {
+    ctx.input(|i| i.raw.dropped_files.clone())
+        .into_iter()
+        .map(|file| {
+            if let Some(bytes) = file.bytes {
+                return Ok(LoadedFile {
+                    name: file.name,
+                    bytes: bytes.to_vec(),
+                });
+            }
+            match file.path {
+                Some(path) => read_path(&path),
+                None => Err(format!("{}: no file contents received", file.name)),
+            }
+        })
+        .collect()
+}
+
+/// Whether files are being dragged over the window.
+pub fn files_hovered(ctx: &egui::Context) -> bool {
+    ctx.input(|i| !i.raw.hovered_files.is_empty())
+}
+
+/// Ask for a file with one of `extensions` with the platform's open dialog;
+/// `kind` names them in the filter.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_dialog(kind: &str, extensions: &[&str]) -> Option<Result<LoadedFile, String>> {
+    let path = rfd::FileDialog::new()
+        .add_filter(kind, extensions)
+        .pick_file()?;
+    Some(read_path(&path))
+}
+
+/// Extension of `name`, lowercased; empty when it has none.
+pub fn extension(name: &str) -> String {
+    name.rsplit_once('.')
+        .map_or("", |(_, ext)| ext)
+        .to_ascii_lowercase()
+}
+
+/// Hand `bytes` to the user as a file called `name`.
+///
+/// Natively this asks where to save with the platform dialog; `Ok(None)`
+/// means the dialog was cancelled. Returns where the file went.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(name: &str, bytes: &[u8]) -> Result<Option<String>, String> {
+    let extension = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(name)
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file()
+    else {
+        return Ok(None);
+    };
+    std::fs::write(&path, bytes).map_err(|err| format!("{}: {err}", path.display()))?;
+    Ok(Some(path.display().to_string()))
+}
+
+/// How long a download's object URL outlives the click that started it.
+#[cfg(target_arch = "wasm32")]
+const REVOKE_DELAY_MS: i32 = 10_000;
+
+/// Hand `bytes` to the user as a file called `name`.
+///
+/// In the browser this wraps the bytes in a `Blob` and clicks a temporary
+/// download link for it.
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(name: &str, bytes: &[u8]) -> Result<Option<String>, String> {
+    use wasm_bindgen::JsCast;
+
+    let js_err = |err: wasm_bindgen::JsValue| format!("{name}: {err:?}");
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/octet-stream");
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(js_err)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_err)?;
+
+    let window = web_sys::window().ok_or_else(|| format!("{name}: no window to download from"))?;
+    let anchor = window
+        .document()
+        .ok_or_else(|| format!("{name}: no document to download from"))?
+        .create_element("a")
+        .map_err(js_err)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| format!("{name}: could not create a download link"))?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+    // revoking right after the click can cancel the download in Firefox and
+    // Safari, so give the browser time to start reading the blob
+    let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )
+        .map_err(js_err)?;
+    Ok(Some(name.to_owned()))
+}
+
+fn read_path(path: &std::path::Path) -> Result<LoadedFile, String> {
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    );
+    std::fs::read(path)
+        .map(|bytes| LoadedFile {
+            name: name.clone(),
+            bytes,
+        })
+        .map_err(|err| format!("{name}: {err}"))
+}
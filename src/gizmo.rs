@@ -0,0 +1,310 @@
+use csgrs::float_types::Real;
+use eframe::egui::{self, Color32, Pos2, Shape, Stroke};
+use glam::{Quat, Vec3};
+use nalgebra::{Matrix3, Matrix4, Point3, Rotation3, Unit, Vector3};
+
+use crate::cpu::Projector;
+use crate::pick::segment_distance;
+use crate::scene::Transform;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    pub const ALL: [Self; 3] = [Self::Translate, Self::Rotate, Self::Scale];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Translate => "Move",
+            Self::Rotate => "Rotate",
+            Self::Scale => "Scale",
+        }
+    }
+}
+
+/// Increments the gizmo rounds drags to.
+#[derive(Clone, Copy, Debug)]
+pub struct Snapping {
+    pub enabled: bool,
+    /// Model units.
+    pub translate: Real,
+    /// Degrees.
+    pub rotate: Real,
+    /// Scale factor steps.
+    pub scale: Real,
+}
+
+impl Default for Snapping {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            translate: 0.25,
+            rotate: 15.0,
+            scale: 0.1,
+        }
+    }
+}
+
+impl Snapping {
+    fn snap(&self, value: Real, step: Real) -> Real {
+        if self.enabled && step > 0.0 {
+            (value / step).round() * step
+        } else {
+            value
+        }
+    }
+}
+
+/// An axis drag in progress.
+struct Drag {
+    axis: usize,
+    start: Transform,
+    pointer: Pos2,
+    /// Rotate mode: screen angle of the pointer last frame, and the total
+    /// turned since the drag started, so turns past half a circle add up.
+    last_angle: f32,
+    turned: f32,
+}
+
+/// What the gizmo did this frame.
+pub struct GizmoOutput {
+    /// The transform was edited.
+    pub changed: bool,
+    /// The pointer is on a handle or dragging one, so the camera should not
+    /// react to it.
+    pub captured: bool,
+    /// Handles to paint over the model.
+    pub shapes: Vec<Shape>,
+}
+
+/// On-canvas handles that edit the selected node's [`Transform`].
+#[derive(Default)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub snap: Snapping,
+    drag: Option<Drag>,
+}
+
+/// Length of the handles on screen.
+const HANDLE_PX: f32 = 90.0;
+/// How close the pointer must be to grab a handle.
+const PICK_PX: f32 = 8.0;
+const RING_SEGMENTS: usize = 48;
+const AXIS_COLORS: [Color32; 3] = [
+    Color32::from_rgb(230, 70, 70),
+    Color32::from_rgb(80, 200, 80),
+    Color32::from_rgb(80, 120, 240),
+];
+const ACTIVE_COLOR: Color32 = Color32::from_rgb(255, 210, 60);
+
+impl Gizmo {
+    /// Handle input and build the handles for a node whose parent space is
+    /// mapped into world space by `parent`.
+    ///
+    /// Moves and rotations follow the world axes; scaling follows the node's
+    /// own axes, since that is where its scale applies.
+    pub fn show(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        projector: &Projector,
+        camera_rotation: Quat,
+        parent: &Matrix4<Real>,
+        transform: &mut Transform,
+    ) -> GizmoOutput {
+        let mut out = GizmoOutput {
+            changed: false,
+            captured: false,
+            shapes: Vec::new(),
+        };
+        let parent_linear: Matrix3<Real> = parent.fixed_view::<3, 3>(0, 0).into();
+        let parent_inverse = parent_linear
+            .try_inverse()
+            .unwrap_or_else(Matrix3::identity);
+        let origin = |t: &Transform| {
+            let p = parent.transform_point(&Point3::from(t.translation));
+            Vec3::new(p.x as f32, p.y as f32, p.z as f32)
+        };
+        let axes = |t: &Transform| -> [Vec3; 3] {
+            std::array::from_fn(|i| match self.mode {
+                GizmoMode::Scale => {
+                    let a = parent_linear * (t.rotation_matrix() * Vector3::ith(i, 1.0));
+                    Vec3::new(a.x as f32, a.y as f32, a.z as f32).normalize_or_zero()
+                }
+                _ => Vec3::AXES[i],
+            })
+        };
+
+        // world length of a handle at the gizmo's depth
+        let handle_length = |o: Vec3| {
+            let (center, depth) = projector.project(o);
+            if depth <= 0.0 {
+                return None;
+            }
+            let right = camera_rotation.inverse() * Vec3::X * 1e-3;
+            let px_per_unit = center.distance(projector.project(o + right).0) / 1e-3;
+            (px_per_unit > 0.0).then(|| HANDLE_PX / px_per_unit)
+        };
+
+        let Some(length) = handle_length(origin(transform)) else {
+            self.drag = None;
+            return out;
+        };
+        let pointer = response.hover_pos();
+        let primary_down = ui.input(|i| i.pointer.primary_down());
+
+        // ── continue or end a drag ──
+        if let Some(drag) = &mut self.drag {
+            if !primary_down {
+                self.drag = None;
+            } else if let Some(pointer) = response.interact_pointer_pos() {
+                let start = &drag.start;
+                let o = origin(start);
+                let axis = axes(start)[drag.axis];
+                let center = projector.project(o).0;
+                let tip = projector.project(o + axis * length).0;
+                let along = tip - center;
+                let dragged = pointer - drag.pointer;
+                let mut next = *start;
+                match self.mode {
+                    GizmoMode::Translate => {
+                        if along.length_sq() > 1.0 {
+                            let amount = (dragged.dot(along) / along.length_sq() * length) as Real;
+                            let amount = self.snap.snap(amount, self.snap.translate);
+                            let world =
+                                Vector3::new(axis.x, axis.y, axis.z).cast::<Real>() * amount;
+                            let local = parent_inverse * world;
+                            for i in 0..3 {
+                                next.translation[i] += local[i];
+                            }
+                        }
+                    }
+                    GizmoMode::Rotate => {
+                        let angle = (pointer - center).angle();
+                        let mut step = angle - drag.last_angle;
+                        if step > std::f32::consts::PI {
+                            step -= std::f32::consts::TAU;
+                        } else if step < -std::f32::consts::PI {
+                            step += std::f32::consts::TAU;
+                        }
+                        drag.turned += step;
+                        drag.last_angle = angle;
+                        // screen y points down, so the angle shrinks as the
+                        // pointer turns counter-clockwise, which is a positive
+                        // turn about an axis facing the viewer
+                        let facing = (camera_rotation * axis).z.signum();
+                        let degrees = (-drag.turned * facing).to_degrees() as Real;
+                        let degrees = self.snap.snap(degrees, self.snap.rotate);
+                        let local_axis = parent_inverse * Vector3::ith(drag.axis, 1.0);
+                        if let Some(local_axis) = Unit::try_new(local_axis, Real::EPSILON) {
+                            let turn =
+                                Rotation3::from_axis_angle(&local_axis, degrees.to_radians());
+                            next.set_rotation_matrix(&(turn * start.rotation_matrix()));
+                        }
+                    }
+                    GizmoMode::Scale => {
+                        if along.length_sq() > 1.0 {
+                            let factor = 1.0 + (dragged.dot(along) / along.length_sq()) as Real;
+                            let scaled = start.scale[drag.axis] * factor;
+                            let min = if self.snap.enabled {
+                                self.snap.scale
+                            } else {
+                                Transform::MIN_SCALE
+                            };
+                            next.scale[drag.axis] =
+                                self.snap.snap(scaled, self.snap.scale).max(min);
+                        }
+                    }
+                }
+                if next != *transform {
+                    *transform = next;
+                    out.changed = true;
+                }
+            }
+        }
+
+        // ── handles for the (possibly updated) transform ──
+        let o = origin(transform);
+        let handles: Vec<Vec<Pos2>> = axes(transform)
+            .iter()
+            .map(|&axis| match self.mode {
+                GizmoMode::Translate | GizmoMode::Scale => {
+                    vec![
+                        projector.project(o).0,
+                        projector.project(o + axis * length).0,
+                    ]
+                }
+                GizmoMode::Rotate => {
+                    let (u, v) = axis.any_orthonormal_pair();
+                    (0..=RING_SEGMENTS)
+                        .map(|k| {
+                            let t = k as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                            projector
+                                .project(o + (u * t.cos() + v * t.sin()) * length * 0.8)
+                                .0
+                        })
+                        .collect()
+                }
+            })
+            .collect();
+
+        let hovered = match (&self.drag, pointer) {
+            (Some(drag), _) => Some(drag.axis),
+            (None, Some(p)) => handles
+                .iter()
+                .enumerate()
+                .map(|(i, line)| (i, distance_to_polyline(p, line)))
+                .filter(|(_, d)| *d < PICK_PX)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i),
+            (None, None) => None,
+        };
+
+        // ── start a drag ──
+        if self.drag.is_none() && response.drag_started_by(egui::PointerButton::Primary) {
+            let press = ui.input(|i| i.pointer.press_origin());
+            if let (Some(axis), Some(press)) = (hovered, press) {
+                self.drag = Some(Drag {
+                    axis,
+                    start: *transform,
+                    pointer: press,
+                    last_angle: (press - projector.project(o).0).angle(),
+                    turned: 0.0,
+                });
+            }
+        }
+        out.captured = hovered.is_some() || self.drag.is_some();
+
+        for (i, line) in handles.into_iter().enumerate() {
+            let color = if hovered == Some(i) {
+                ACTIVE_COLOR
+            } else {
+                AXIS_COLORS[i]
+            };
+            let stroke = Stroke::new(2.5, color);
+            let tip = *line.last().unwrap_or(&Pos2::ZERO);
+            out.shapes.push(Shape::line(line, stroke));
+            match self.mode {
+                GizmoMode::Translate => out.shapes.push(Shape::circle_filled(tip, 5.0, color)),
+                GizmoMode::Scale => out.shapes.push(Shape::rect_filled(
+                    egui::Rect::from_center_size(tip, egui::vec2(9.0, 9.0)),
+                    0.0,
+                    color,
+                )),
+                GizmoMode::Rotate => {}
+            }
+        }
+        out
+    }
+}
+
+fn distance_to_polyline(p: Pos2, line: &[Pos2]) -> f32 {
+    line.windows(2)
+        .map(|w| segment_distance(p, w[0], w[1]))
+        .fold(f32::INFINITY, f32::min)
+}
@@ -0,0 +1,372 @@
+use eframe::glow::{self, HasContext};
+use glam::{Mat4, Vec3, Vec4};
+use std::sync::Arc;
+
+use crate::DisplayMode;
+use crate::edges::EdgeSet;
+use crate::mesh::TriMesh;
+
+/// Geometry handed to the GPU. Shared with the paint callback by `Arc`, and
+/// re-uploaded only when `generation` changes.
+#[derive(Clone, Debug, Default)]
+pub struct GpuMesh {
+    pub generation: u64,
+    pub mesh: Arc<TriMesh>,
+    pub edges: Arc<EdgeSet>,
+}
+
+/// Per-frame state for one draw of the model.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameUniforms {
+    /// Clip-space transform of the viewport the callback paints into.
+    pub view_projection: Mat4,
+    /// Model-to-view rotation, used to bring normals into view space.
+    pub rotation: Mat4,
+    /// Viewport size in physical pixels, for the dash pattern.
+    pub viewport_px: [f32; 2],
+    pub mode: DisplayMode,
+    /// Draw edges hidden behind faces dashed (hidden-line mode only).
+    pub dashed_hidden: bool,
+    pub base_color: Vec3,
+    pub edge_color: Vec4,
+    pub hidden_color: Vec4,
+}
+
+/// Owns the GL programs and buffers for the model.
+///
+/// Lives behind an `Arc<Mutex<_>>` so it can be reached from the
+/// `egui::PaintCallback`, which runs after `update` returns.
+pub struct GpuRenderer {
+    program: glow::Program,
+    u_mvp: Option<glow::UniformLocation>,
+    u_rotation: Option<glow::UniformLocation>,
+    u_color: Option<glow::UniformLocation>,
+    u_lit: Option<glow::UniformLocation>,
+    line_program: glow::Program,
+    u_line_mvp: Option<glow::UniformLocation>,
+    u_line_viewport: Option<glow::UniformLocation>,
+    u_line_color: Option<glow::UniformLocation>,
+    u_line_dash: Option<glow::UniformLocation>,
+    triangles: Buffers,
+    lines: Buffers,
+    generation: Option<u64>,
+}
+
+struct Buffers {
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    ebo: glow::Buffer,
+    count: i32,
+}
+
+const A_POS: u32 = 0;
+/// Normal for triangles, the opposite endpoint for lines.
+const A_SECOND: u32 = 1;
+
+/// Dash and gap length of hidden edges, in physical pixels.
+const DASH_PX: f32 = 6.0;
+
+const VERTEX_SHADER: &str = r#"
+    uniform mat4 u_mvp;
+    uniform mat4 u_rotation;
+    in vec3 a_pos;
+    in vec3 a_normal;
+    out vec3 v_normal;
+    void main() {
+        v_normal = mat3(u_rotation) * a_normal;
+        gl_Position = u_mvp * vec4(a_pos, 1.0);
+    }
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+    uniform vec4 u_color;
+    uniform float u_lit;
+    in vec3 v_normal;
+    out vec4 out_color;
+    const float AMBIENT = 0.2;
+    const float SHININESS = 32.0;
+    void main() {
+        if (u_lit < 0.5) {
+            out_color = u_color;
+            return;
+        }
+        vec3 light = normalize(vec3(-0.3, 0.4, 1.0));
+        vec3 n = normalize(v_normal);
+        // light both sides so open shells and inverted faces stay readable
+        if (n.z < 0.0) {
+            n = -n;
+        }
+        float diffuse = max(dot(n, light), 0.0);
+        vec3 reflected = reflect(-light, n);
+        float specular = pow(max(reflected.z, 0.0), SHININESS) * 0.35;
+        vec3 c = u_color.rgb * (AMBIENT + (1.0 - AMBIENT) * diffuse) + vec3(specular);
+        out_color = vec4(clamp(c, 0.0, 1.0), 1.0);
+    }
+"#;
+
+const LINE_VERTEX_SHADER: &str = r#"
+    uniform mat4 u_mvp;
+    uniform vec2 u_viewport;
+    in vec3 a_pos;
+    in vec3 a_other;
+    out float v_dist;
+    void main() {
+        vec4 p = u_mvp * vec4(a_pos, 1.0);
+        vec4 o = u_mvp * vec4(a_other, 1.0);
+        vec2 screen_p = p.xy / p.w * 0.5 * u_viewport;
+        vec2 screen_o = o.xy / o.w * 0.5 * u_viewport;
+        // the first vertex of each segment starts the dash pattern
+        v_dist = gl_VertexID % 2 == 0 ? 0.0 : length(screen_p - screen_o);
+        gl_Position = p;
+    }
+"#;
+
+const LINE_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+    uniform vec4 u_color;
+    uniform float u_dash;
+    in float v_dist;
+    out vec4 out_color;
+    void main() {
+        if (u_dash > 0.0 && mod(v_dist, 2.0 * u_dash) > u_dash) {
+            discard;
+        }
+        out_color = u_color;
+    }
+"#;
+
+impl GpuRenderer {
+    pub fn new(gl: &glow::Context) -> Result<Self, String> {
+        let program = link_program(gl, VERTEX_SHADER, FRAGMENT_SHADER, ["a_pos", "a_normal"])?;
+        let line_program = link_program(
+            gl,
+            LINE_VERTEX_SHADER,
+            LINE_FRAGMENT_SHADER,
+            ["a_pos", "a_other"],
+        )?;
+        unsafe {
+            Ok(Self {
+                u_mvp: gl.get_uniform_location(program, "u_mvp"),
+                u_rotation: gl.get_uniform_location(program, "u_rotation"),
+                u_color: gl.get_uniform_location(program, "u_color"),
+                u_lit: gl.get_uniform_location(program, "u_lit"),
+                program,
+                u_line_mvp: gl.get_uniform_location(line_program, "u_mvp"),
+                u_line_viewport: gl.get_uniform_location(line_program, "u_viewport"),
+                u_line_color: gl.get_uniform_location(line_program, "u_color"),
+                u_line_dash: gl.get_uniform_location(line_program, "u_dash"),
+                line_program,
+                triangles: Buffers::new(gl)?,
+                lines: Buffers::new(gl)?,
+                generation: None,
+            })
+        }
+    }
+
+    /// Upload `mesh` unless this generation is already on the GPU.
+    pub fn upload(&mut self, gl: &glow::Context, mesh: &GpuMesh) {
+        if self.generation == Some(mesh.generation) {
+            return;
+        }
+        self.generation = Some(mesh.generation);
+
+        let mut vertices = Vec::with_capacity(mesh.mesh.positions.len() * 6);
+        for (p, n) in mesh.mesh.positions.iter().zip(&mesh.mesh.normals) {
+            vertices.extend_from_slice(&[p.x, p.y, p.z, n.x, n.y, n.z]);
+        }
+        self.triangles.fill(gl, &vertices, &mesh.mesh.indices);
+
+        // lines are expanded so each vertex can carry its opposite endpoint
+        let mut points = Vec::with_capacity(mesh.edges.edges.len() * 12);
+        for (a, b) in mesh.edges.segments() {
+            points.extend_from_slice(&[a.x, a.y, a.z, b.x, b.y, b.z]);
+            points.extend_from_slice(&[b.x, b.y, b.z, a.x, a.y, a.z]);
+        }
+        let indices: Vec<u32> = (0..mesh.edges.edges.len() as u32 * 2).collect();
+        self.lines.fill(gl, &points, &indices);
+    }
+
+    pub fn paint(&self, gl: &glow::Context, frame: &FrameUniforms) {
+        let mvp = frame.view_projection.to_cols_array();
+        unsafe {
+            // the scissor is already set to the callback's clip rect, so this
+            // only clears the depth under the canvas
+            gl.clear_depth_f32(1.0);
+            gl.clear(glow::DEPTH_BUFFER_BIT);
+            gl.enable(glow::DEPTH_TEST);
+            gl.depth_func(glow::LEQUAL);
+            gl.disable(glow::BLEND);
+
+            gl.use_program(Some(self.program));
+            gl.uniform_matrix_4_f32_slice(self.u_mvp.as_ref(), false, &mvp);
+            gl.uniform_matrix_4_f32_slice(
+                self.u_rotation.as_ref(),
+                false,
+                &frame.rotation.to_cols_array(),
+            );
+
+            if frame.mode != DisplayMode::Wireframe {
+                // push faces back a little so edges on them win the depth test
+                gl.enable(glow::POLYGON_OFFSET_FILL);
+                gl.polygon_offset(1.0, 1.0);
+                // hidden-line mode only needs the faces' depth, not their colour
+                let depth_only = frame.mode == DisplayMode::HiddenLine;
+                if depth_only {
+                    gl.color_mask(false, false, false, false);
+                }
+                let c = frame.base_color;
+                gl.uniform_4_f32(self.u_color.as_ref(), c.x, c.y, c.z, 1.0);
+                gl.uniform_1_f32(self.u_lit.as_ref(), 1.0);
+                self.triangles.draw(gl, glow::TRIANGLES);
+                if depth_only {
+                    gl.color_mask(true, true, true, true);
+                }
+                gl.disable(glow::POLYGON_OFFSET_FILL);
+            }
+
+            if frame.mode != DisplayMode::Shaded {
+                gl.use_program(Some(self.line_program));
+                gl.uniform_matrix_4_f32_slice(self.u_line_mvp.as_ref(), false, &mvp);
+                let [w, h] = frame.viewport_px;
+                gl.uniform_2_f32(self.u_line_viewport.as_ref(), w, h);
+
+                let c = frame.edge_color;
+                gl.uniform_4_f32(self.u_line_color.as_ref(), c.x, c.y, c.z, c.w);
+                gl.uniform_1_f32(self.u_line_dash.as_ref(), 0.0);
+                self.lines.draw(gl, glow::LINES);
+
+                if frame.mode == DisplayMode::HiddenLine && frame.dashed_hidden {
+                    gl.depth_func(glow::GREATER);
+                    gl.depth_mask(false);
+                    let c = frame.hidden_color;
+                    gl.uniform_4_f32(self.u_line_color.as_ref(), c.x, c.y, c.z, c.w);
+                    gl.uniform_1_f32(self.u_line_dash.as_ref(), DASH_PX);
+                    self.lines.draw(gl, glow::LINES);
+                    gl.depth_mask(true);
+                }
+            }
+
+            gl.bind_vertex_array(None);
+            gl.use_program(None);
+            gl.disable(glow::DEPTH_TEST);
+        }
+    }
+
+    pub fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_program(self.program);
+            gl.delete_program(self.line_program);
+        }
+        self.triangles.destroy(gl);
+        self.lines.destroy(gl);
+    }
+}
+
+impl Buffers {
+    /// Interleaved `a_pos` + second attribute, three floats each.
+    fn new(gl: &glow::Context) -> Result<Self, String> {
+        unsafe {
+            let vao = gl.create_vertex_array()?;
+            let vbo = gl.create_buffer()?;
+            let ebo = gl.create_buffer()?;
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+            let stride = 6 * std::mem::size_of::<f32>() as i32;
+            gl.enable_vertex_attrib_array(A_POS);
+            gl.vertex_attrib_pointer_f32(A_POS, 3, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(A_SECOND);
+            gl.vertex_attrib_pointer_f32(A_SECOND, 3, glow::FLOAT, false, stride, 12);
+            gl.bind_vertex_array(None);
+
+            Ok(Self {
+                vao,
+                vbo,
+                ebo,
+                count: 0,
+            })
+        }
+    }
+
+    fn fill(&mut self, gl: &glow::Context, vertices: &[f32], indices: &[u32]) {
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                bytemuck::cast_slice(vertices),
+                glow::STATIC_DRAW,
+            );
+            gl.buffer_data_u8_slice(
+                glow::ELEMENT_ARRAY_BUFFER,
+                bytemuck::cast_slice(indices),
+                glow::STATIC_DRAW,
+            );
+            gl.bind_vertex_array(None);
+        }
+        self.count = indices.len() as i32;
+    }
+
+    fn draw(&self, gl: &glow::Context, mode: u32) {
+        if self.count == 0 {
+            return;
+        }
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao));
+            gl.draw_elements(mode, self.count, glow::UNSIGNED_INT, 0);
+        }
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_buffer(self.ebo);
+        }
+    }
+}
+
+/// Compile and link a program, binding `attribs` to `A_POS` and `A_SECOND`.
+fn link_program(
+    gl: &glow::Context,
+    vertex: &str,
+    fragment: &str,
+    attribs: [&str; 2],
+) -> Result<glow::Program, String> {
+    let shader_version = if cfg!(target_arch = "wasm32") {
+        "#version 300 es"
+    } else {
+        "#version 330"
+    };
+
+    unsafe {
+        let program = gl.create_program()?;
+        let mut shaders = Vec::new();
+        for (kind, source) in [
+            (glow::VERTEX_SHADER, vertex),
+            (glow::FRAGMENT_SHADER, fragment),
+        ] {
+            let shader = gl.create_shader(kind)?;
+            gl.shader_source(shader, &format!("{shader_version}\n{source}"));
+            gl.compile_shader(shader);
+            if !gl.get_shader_compile_status(shader) {
+                return Err(gl.get_shader_info_log(shader));
+            }
+            gl.attach_shader(program, shader);
+            shaders.push(shader);
+        }
+        gl.bind_attrib_location(program, A_POS, attribs[0]);
+        gl.bind_attrib_location(program, A_SECOND, attribs[1]);
+        gl.link_program(program);
+        if !gl.get_program_link_status(program) {
+            return Err(gl.get_program_info_log(program));
+        }
+        for shader in shaders {
+            gl.detach_shader(program, shader);
+            gl.delete_shader(shader);
+        }
+        Ok(program)
+    }
+}
@@ -0,0 +1,93 @@
+use crate::scene::{NodeId, Scene};
+
+/// Oldest edits are dropped beyond this many steps.
+const MAX_STEPS: usize = 100;
+
+/// The model as it was before (or after) one edit.
+struct Step {
+    label: String,
+    scene: Scene,
+    selected: Option<NodeId>,
+}
+
+/// Undo/redo stacks of whole-scene snapshots.
+///
+/// Snapshots rather than one command per kind of edit: the tree editor,
+/// gizmo and sliders all change the scene in place, and comparing it with
+/// the last snapshot catches every edit without each of them describing
+/// itself, so undo cannot miss one or apply it differently. Imported meshes
+/// are shared between snapshots, so a step costs little more than the tree
+/// itself. Edits made during one gesture — a slider or gizmo
+/// drag, or typing into a field — are coalesced into a single step.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// The newest undo step may still absorb edits with the same label.
+    open: bool,
+}
+
+impl History {
+    /// Remember `before`, the state the edit called `label` started from.
+    ///
+    /// With `coalesce`, a following edit with the same label extends this
+    /// step instead of starting a new one.
+    pub fn record(&mut self, label: &str, before: Scene, selected: Option<NodeId>, coalesce: bool) {
+        self.redo.clear();
+        let extends = self.open && self.undo.last().is_some_and(|s| s.label == label);
+        if !extends {
+            self.undo.push(Step {
+                label: label.to_owned(),
+                scene: before,
+                selected,
+            });
+            if self.undo.len() > MAX_STEPS {
+                self.undo.remove(0);
+            }
+        }
+        self.open = coalesce;
+    }
+
+    /// End the current gesture, so the next edit starts a new step.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Swap `scene` for the state before the latest edit. Returns the label
+    /// of the undone edit.
+    pub fn undo(&mut self, scene: &mut Scene, selected: &mut Option<NodeId>) -> Option<String> {
+        let step = self.undo.pop()?;
+        let label = step.label.clone();
+        self.redo.push(swap(step, scene, selected));
+        self.open = false;
+        Some(label)
+    }
+
+    /// Re-apply the latest undone edit. Returns its label.
+    pub fn redo(&mut self, scene: &mut Scene, selected: &mut Option<NodeId>) -> Option<String> {
+        let step = self.redo.pop()?;
+        let label = step.label.clone();
+        self.undo.push(swap(step, scene, selected));
+        self.open = false;
+        Some(label)
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.last().map(|s| s.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().map(|s| s.label.as_str())
+    }
+}
+
+/// Put `step` in place and return the state it replaced under the same label.
+fn swap(step: Step, scene: &mut Scene, selected: &mut Option<NodeId>) -> Step {
+    let previous = Step {
+        label: step.label,
+        scene: std::mem::replace(scene, step.scene),
+        selected: *selected,
+    };
+    *selected = step.selected.filter(|&id| scene.root.find(id).is_some());
+    previous
+}
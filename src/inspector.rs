@@ -0,0 +1,157 @@
+use csgrs::csg::CSG;
+use csgrs::float_types::Real;
+use csgrs::float_types::parry3d::shape::{Shape, TriMesh as ParryMesh};
+use eframe::egui;
+use nalgebra::{Matrix3, Point3};
+
+use crate::scene::Units;
+use crate::validate::Report;
+use crate::weld::WeldedMesh;
+
+/// Geometry facts about the evaluated model, computed once per change.
+pub struct Properties {
+    pub polygons: usize,
+    pub triangles: usize,
+    /// After welding, so shared corners count once.
+    pub vertices: usize,
+    pub edges: usize,
+    /// Smallest and largest corner; `None` for an empty model.
+    pub bounds: Option<(Point3<Real>, Point3<Real>)>,
+    pub area: Real,
+    /// Closed and consistently oriented, per [`Report::check`].
+    pub watertight: bool,
+    /// Volume and inertia at unit density; mass and inertia scale linearly
+    /// with it. `None` when the model is not watertight or encloses no
+    /// volume.
+    pub solid: Option<Solid>,
+}
+
+pub struct Solid {
+    pub volume: Real,
+    pub centroid: Point3<Real>,
+    /// About the centroid, along the model axes, at unit density.
+    pub inertia: Matrix3<Real>,
+}
+
+impl Properties {
+    /// Statistics of `csg` itself, not of the (possibly sectioned) mesh on
+    /// screen.
+    pub fn of(csg: &CSG<()>, weld_tolerance: Real) -> Self {
+        let welded = WeldedMesh::from_csg(csg, weld_tolerance);
+        let area = (0..welded.polygons.len())
+            .map(|p| welded.polygon_area(p))
+            .sum();
+        let empty = csg.polygons.is_empty();
+        // an open surface has no inside, so its "volume" would be noise
+        let watertight = Report::check(&welded).is_clean();
+        let bounds = (!empty).then(|| {
+            let aabb = csg.bounding_box();
+            (aabb.mins, aabb.maxs)
+        });
+        Self {
+            polygons: csg.polygons.len(),
+            // a simple polygon with n corners always splits into n - 2
+            triangles: csg
+                .polygons
+                .iter()
+                .map(|p| p.vertices.len().saturating_sub(2))
+                .sum(),
+            vertices: welded.vertices.len(),
+            edges: welded.edges().len(),
+            bounds,
+            area,
+            watertight,
+            solid: if empty || !watertight {
+                None
+            } else {
+                Solid::of(&welded)
+            },
+        }
+    }
+}
+
+impl Solid {
+    /// Integrates over the welded polygons, fanned into triangles.
+    /// `CSG::mass_properties` does the same but keeps only the principal
+    /// frame and panics where parry rejects the mesh, so the mesh is built
+    /// and the tensor rebuilt here.
+    fn of(welded: &WeldedMesh) -> Option<Self> {
+        let triangles = welded
+            .polygons
+            .iter()
+            .flat_map(|ring| {
+                (1..ring.len().saturating_sub(1)).map(|k| [ring[0], ring[k], ring[k + 1]])
+            })
+            .collect();
+        let mesh = ParryMesh::new(welded.vertices.clone(), triangles).ok()?;
+        let props = mesh.mass_properties(1.0);
+        let volume = props.mass();
+        (volume > 0.0 && volume.is_finite()).then(|| Self {
+            volume,
+            centroid: props.local_com,
+            inertia: props.reconstruct_inertia_matrix(),
+        })
+    }
+}
+
+/// Side-panel readout in `units`; `density` is edited in place.
+pub fn inspector_ui(ui: &mut egui::Ui, props: &Properties, units: Units, density: &mut Real) {
+    egui::Grid::new("mesh stats").num_columns(2).show(ui, |ui| {
+        row(ui, "Polygons", props.polygons.to_string());
+        row(ui, "Triangles", props.triangles.to_string());
+        row(ui, "Vertices", props.vertices.to_string());
+        row(ui, "Edges", props.edges.to_string());
+        row(ui, "Surface area", units.format(props.area, 2, 4));
+        if let Some((min, max)) = &props.bounds {
+            row(ui, "Bounds min", point(min, units));
+            row(ui, "Bounds max", point(max, units));
+            row(ui, "Size", point(&Point3::from(max - min), units));
+        }
+    });
+
+    ui.separator();
+    let Some(solid) = &props.solid else {
+        if props.watertight {
+            ui.weak("The model encloses no volume.");
+        } else {
+            ui.weak("Mass properties need a closed mesh; Check mesh shows the gaps.");
+        }
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.label("Density");
+        ui.add(
+            egui::DragValue::new(density)
+                .speed(0.01)
+                .clamp_range(0.0..=Real::MAX),
+        );
+    });
+    egui::Grid::new("mass properties")
+        .num_columns(2)
+        .show(ui, |ui| {
+            row(ui, "Volume", units.format(solid.volume, 3, 4));
+            row(ui, "Mass", format!("{:.4}", solid.volume * *density));
+            row(ui, "Centroid", point(&solid.centroid, units));
+        });
+    ui.label("Inertia tensor about the centroid");
+    egui::Grid::new("inertia").num_columns(3).show(ui, |ui| {
+        for r in 0..3 {
+            for c in 0..3 {
+                ui.monospace(format!("{:.4}", solid.inertia[(r, c)] * *density));
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn row(ui: &mut egui::Ui, label: &str, value: String) {
+    ui.label(label);
+    ui.monospace(value);
+    ui.end_row();
+}
+
+fn point(p: &Point3<Real>, units: Units) -> String {
+    format!("{:.3}, {:.3}, {:.3} {}", p.x, p.y, p.z, units.symbol())
+        .trim_end()
+        .to_owned()
+}
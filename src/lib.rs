@@ -1,109 +1,1218 @@
 use eframe::egui;
-use glam::{Quat, Vec3};
+use glam::{Mat4, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
 use csgrs::csg::CSG;
-use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+pub mod camera;
+pub mod cpu;
+pub mod edges;
+pub mod files;
+pub mod gizmo;
+pub mod gpu;
+pub mod history;
+pub mod inspector;
+pub mod measure;
+pub mod mesh;
+pub mod persist;
+pub mod pick;
+pub mod primitives;
+pub mod project;
+pub mod scene;
+pub mod section;
+pub mod share;
+pub mod tree_editor;
+pub mod validate;
+pub mod weld;
+
+use camera::{
+    Camera, EYE_DIST, NamedView, OrbitMode, Projection, Touches, ViewTransition,
+};
+use gpu::{FrameUniforms, GpuMesh, GpuRenderer};
+use csgrs::float_types::Real;
+use edges::EdgeSet;
+use mesh::TriMesh;
+use pick::Pick;
+use files::LoadedFile;
+use gizmo::{Gizmo, GizmoMode};
+use history::History;
+use measure::Measure;
+use scene::{ImportedMesh, NodeId, NodeKind, Scene};
+use section::Section;
+use weld::WeldedMesh;
+
+/// How the model is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    /// Filled, lit triangles.
+    Shaded,
+    /// Lit triangles with the visible feature edges on top.
+    ShadedEdges,
+    /// Every feature edge, front and back.
+    Wireframe,
+    /// Only the edges not hidden by a face, like a technical drawing.
+    HiddenLine,
+}
+
+impl DisplayMode {
+    pub const ALL: [Self; 4] = [Self::Shaded, Self::ShadedEdges, Self::Wireframe, Self::HiddenLine];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Shaded => "Shaded",
+            Self::ShadedEdges => "Shaded + edges",
+            Self::Wireframe => "Wireframe",
+            Self::HiddenLine => "Hidden line",
+        }
+    }
+}
+
+/// What gets drawn on the canvas.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub mode: DisplayMode,
+    /// In hidden-line mode, draw the occluded edges dashed instead of
+    /// dropping them.
+    pub dashed_hidden: bool,
+    /// Edges between faces meeting at less than this many degrees are hidden.
+    pub crease_angle: f32,
+    /// Vertices closer than this (in model units) are treated as one when
+    /// extracting edges.
+    pub weld_tolerance: Real,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Shaded,
+            dashed_hidden: false,
+            crease_angle: 20.0,
+            weld_tolerance: 1e-5,
+        }
+    }
+}
+
+/// Diffuse colour of the shaded solid, shared by the CPU and GPU paths.
+const BASE_COLOR: Vec3 = Vec3::new(0.62, 0.68, 0.78);
+
+/// Highlight of the face, edge or vertex under the pointer.
+const HOVER_COLOR: egui::Color32 = egui::Color32::from_rgb(120, 200, 255);
+
+/// Highlight of the clicked face, edge or vertex.
+const PICKED_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 150, 40);
+
+/// Framing puts the eye this many bounding radii from the model.
+const FIT_DISTANCE: f32 = 2.0;
+
+/// Framing makes the model span this much of the shorter canvas side.
+const FIT_FILL: f32 = 0.85;
+
+/// Zooming out stops once the model spans 1/`ZOOM_OUT_LIMIT` of the view.
+const ZOOM_OUT_LIMIT: f32 = 20.0;
+
+/// Zooming in stops once 1/`ZOOM_IN_LIMIT` of the model fills the view.
+const ZOOM_IN_LIMIT: f32 = 1e4;
 
-#[derive(Default)]
 pub struct CsgrsApp {
-    rotation: Quat,
-    translation: egui::Vec2,
-    zoom: f32,
-    edges: Vec<(Vec3, Vec3)>,
+    camera: Camera,
+    /// Point held still while orbiting, picked when the drag starts.
+    pivot: Option<Vec3>,
+    /// Animation towards a named view, overriding `rotation` while it runs.
+    transition: Option<ViewTransition>,
+    orbit: OrbitMode,
+    touches: Touches,
+    /// Construction tree; `csg` is its evaluation.
+    scene: Scene,
+    /// Node shown in the properties editor and carrying the gizmo.
+    selected: Option<NodeId>,
+    /// Bounding sphere of a selected node as placed in the world, with the
+    /// node it is for; dropped whenever the model changes.
+    selection_sphere: Option<(NodeId, Option<(Vec3, f32)>)>,
+    gizmo: Gizmo,
+    history: History,
+    /// `scene` as last recorded in `history`; edits are found by comparing
+    /// against it.
+    recorded: Scene,
+    /// `selected` as last recorded, i.e. before the pending edit.
+    recorded_selected: Option<NodeId>,
+    csg: CSG<()>,
+    /// Bounding sphere of `csg`, kept for framing and zoom limits.
+    sphere: Option<(Vec3, f32)>,
+    /// Clipping plane through the model; only changes what is drawn.
+    section: Section,
+    /// `csg` as drawn and picked: cut and capped while `section` is enabled.
+    shown: CSG<()>,
+    /// Hatching across the section cap, in model space.
+    hatch: Vec<[nalgebra::Point3<Real>; 2]>,
+    mesh: Arc<TriMesh>,
+    /// `shown` with coincident vertices merged; the basis for edge analysis.
+    welded: WeldedMesh,
+    /// Speeds up picking on `welded`.
+    bvh: pick::Bvh,
+    edges: Arc<EdgeSet>,
+    /// Face, edge or vertex under the pointer.
+    hovered: Option<Pick>,
+    /// Face, edge or vertex last clicked.
+    picked: Option<Pick>,
+    /// Picks being measured; while active, clicks go here instead of `picked`.
+    measure: Measure,
+    show_inspector: bool,
+    density: Real,
+    /// Statistics of `csg`, computed when the inspector is shown and dropped
+    /// whenever the model changes.
+    properties: Option<inspector::Properties>,
+    /// Highlight open, non-manifold and flipped edges.
+    check_mesh: bool,
+    /// Result of the mesh check, dropped whenever the model changes.
+    report: Option<validate::Report>,
+    /// Bumped whenever `mesh`/`edges` change so the GPU copy gets refreshed.
+    generation: u64,
+    options: RenderOptions,
+    /// Last notice for the status bar, e.g. a failed import.
+    status: Option<String>,
+    /// A copied link is showing in the address bar; it goes at the next
+    /// edit so a reload does not bring back the linked model.
+    link_in_address_bar: bool,
+    /// The last save could not keep the scene; it is only reported when
+    /// that starts.
+    scene_unsaved: bool,
+    /// `None` when no GL context is available; drawing then falls back to
+    /// the CPU painter.
+    gpu: Option<Arc<Mutex<GpuRenderer>>>,
 }
 
 impl CsgrsApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        // ── build a cube with csgrs and collect its unique edges ──────────────
-        let mut uniq: HashSet<((i64, i64, i64), (i64, i64, i64))> = HashSet::new();
-        //let cube = CSG::<()>::cube(2.0, 2.0, 2.0, None).center();
-        let cube = CSG::<()>::icosahedron(2.0, None).center();
-
-        for poly in &cube.polygons {
-            for (a, b) in poly.edges() {
-                // key ≤---> canonicalised (small-grid-snapped) pair
-                let snap = |p: &csgrs::float_types::Real| (*p * 1e5).round() as i64;
-                let key = {
-                    let ka = (snap(&a.pos.x), snap(&a.pos.y), snap(&a.pos.z));
-                    let kb = (snap(&b.pos.x), snap(&b.pos.y), snap(&b.pos.z));
-                    if ka < kb { (ka, kb) } else { (kb, ka) }
-                };
-                uniq.insert(key);
-            }
-        }
-
-        let edges = uniq
-            .into_iter()
-            .map(|(ka, kb)| {
-                let v = |(x, y, z): (i64, i64, i64)| Vec3::new(x as f32, y as f32, z as f32) / 1e5;
-                (v(ka), v(kb))
-            })
-            .collect();
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let gpu = cc.gl.as_ref().and_then(|gl| match GpuRenderer::new(gl) {
+            Ok(renderer) => Some(Arc::new(Mutex::new(renderer))),
+            Err(err) => {
+                log::error!("GL renderer unavailable, using the CPU painter: {err}");
+                None
+            }
+        });
 
-        Self {
-            rotation: Quat::IDENTITY,
-            translation: egui::Vec2::ZERO,
-            zoom: 1.0,
-            edges,
+        let scene = Scene::default();
+
+        let mut app = Self {
+            camera: Camera::default(),
+            pivot: None,
+            transition: None,
+            orbit: OrbitMode::default(),
+            touches: Touches::default(),
+            recorded: scene.clone(),
+            recorded_selected: None,
+            scene,
+            selected: None,
+            selection_sphere: None,
+            gizmo: Gizmo::default(),
+            history: History::default(),
+            csg: CSG::new(),
+            sphere: None,
+            section: Section::default(),
+            shown: CSG::new(),
+            hatch: Vec::new(),
+            mesh: Arc::default(),
+            welded: WeldedMesh::default(),
+            bvh: pick::Bvh::default(),
+            edges: Arc::default(),
+            hovered: None,
+            picked: None,
+            measure: Measure::default(),
+            show_inspector: false,
+            density: 1.0,
+            properties: None,
+            check_mesh: false,
+            report: None,
+            generation: 0,
+            options: RenderOptions::default(),
+            status: None,
+            link_in_address_bar: false,
+            scene_unsaved: false,
+            gpu,
+        };
+        let saved: Option<persist::Saved> =
+            cc.storage.and_then(|s| eframe::get_value(s, eframe::APP_KEY));
+        let restored = saved.is_some();
+        if let Some(saved) = saved {
+            saved.restore(&mut app, cc.storage.and_then(persist::load_scene));
+        }
+        app.set_csg(app.scene.evaluate());
+        if !restored {
+            app.fit_all();
+        }
+        app
+    }
+
+    /// Replace the model, rebuilding the render buffers.
+    pub fn set_csg(&mut self, csg: CSG<()>) {
+        self.sphere = camera::bounding_sphere(&csg);
+        self.csg = csg;
+        self.properties = None;
+        self.selection_sphere = None;
+        if std::mem::take(&mut self.link_in_address_bar) {
+            #[cfg(target_arch = "wasm32")]
+            share::clear_page_fragment();
+        }
+        self.recut();
+    }
+
+    /// Re-apply the section plane, e.g. after it moved.
+    fn recut(&mut self) {
+        if self.section.enabled {
+            let cut = self.section.apply(&self.csg);
+            self.shown = cut.csg;
+            self.hatch = cut.hatch;
+        } else {
+            self.shown = self.csg.clone();
+            self.hatch.clear();
+        }
+        self.mesh = Arc::new(TriMesh::from_csg(&self.shown));
+        self.reweld();
+    }
+
+    /// Smallest and largest corner of the whole model, uncut.
+    fn bounds(&self) -> Option<(nalgebra::Point3<Real>, nalgebra::Point3<Real>)> {
+        (!self.csg.polygons.is_empty()).then(|| {
+            let aabb = self.csg.bounding_box();
+            (aabb.mins, aabb.maxs)
+        })
+    }
+
+    /// Open a dropped or picked file: a project by its extension, otherwise
+    /// an STL mesh.
+    fn open_file(&mut self, file: LoadedFile) {
+        if files::extension(&file.name) == project::EXTENSION {
+            self.open_project(file);
+        } else {
+            self.open_stl(file);
+        }
+    }
+
+    /// Replace the scene with the mesh in `file`.
+    fn open_stl(&mut self, file: LoadedFile) {
+        match ImportedMesh::from_stl(&file.name, &file.bytes) {
+            Ok(mesh) => {
+                let polygons = mesh.csg.polygons.len();
+                self.scene = Scene::with_leaf(NodeKind::Mesh(mesh));
+                self.selected = None;
+                self.record_edit(&format!("open {}", file.name), false);
+                self.set_csg(self.scene.evaluate());
+                self.fit_all();
+                self.status = Some(format!("Opened {} ({polygons} triangles)", file.name));
+            }
+            Err(err) => self.report(format!("Could not read {} as STL: {err}", file.name)),
+        }
+    }
+
+    /// Replace the scene and view with those in a shared `link` (or its
+    /// fragment). Returns whether it held a model, readable or not.
+    pub fn open_link(&mut self, link: &str) -> bool {
+        let shared = match share::Shared::from_link(link) {
+            Some(Ok(shared)) => shared,
+            Some(Err(err)) => {
+                self.report(format!("Could not open the shared link: {err}"));
+                return true;
+            }
+            None => return false,
+        };
+        self.open_scene(shared.scene, shared.camera, "shared link");
+        true
+    }
+
+    /// Replace the scene and view with those saved in a project `file`.
+    fn open_project(&mut self, file: LoadedFile) {
+        let project = std::str::from_utf8(&file.bytes)
+            .map_err(|e| e.to_string())
+            .and_then(project::Project::from_json);
+        match project {
+            Ok(project) => self.open_scene(project.scene, project.camera, &file.name),
+            Err(err) => self.report(format!("Could not read {} as a project: {err}", file.name)),
+        }
+    }
+
+    /// Switch to a whole saved scene, seen as it was saved; undoable.
+    fn open_scene(&mut self, scene: Scene, camera: Camera, source: &str) {
+        self.scene = scene;
+        self.selected = None;
+        self.record_edit(&format!("open {source}"), false);
+        self.set_csg(self.scene.evaluate());
+        self.camera = camera;
+        self.transition = None;
+        self.status = Some(format!("Opened {source}"));
+    }
+
+    /// Write the scene and view to a project file.
+    fn save_project(&mut self) {
+        let project = project::Project::new(self.scene.clone(), self.camera);
+        let json = match project.to_json() {
+            Ok(json) => json,
+            Err(err) => return self.report(format!("Could not write the project: {err}")),
+        };
+        match files::save_file(&format!("model.{}", project::EXTENSION), json.as_bytes()) {
+            Ok(Some(path)) => self.status = Some(format!("Saved {path}")),
+            Ok(None) => {}
+            Err(err) => self.report(format!("Could not save the project: {err}")),
+        }
+    }
+
+    /// Put a link to the current scene and view on the clipboard and, on
+    /// the web, in the address bar.
+    fn copy_link(&mut self, ctx: &egui::Context) {
+        let shared = share::Shared::new(self.scene.clone(), self.camera);
+        match shared.to_fragment() {
+            Ok(fragment) => {
+                let link = share::link(&fragment);
+                let length = link.len();
+                ctx.copy_text(link);
+                // the browser may still refuse the clipboard, and says so
+                // only in the console
+                #[cfg(target_arch = "wasm32")]
+                {
+                    share::set_page_fragment(&fragment);
+                    self.link_in_address_bar = true;
+                    self.status = Some(format!(
+                        "Link ({length} characters) put in the address bar and sent to \
+                         the clipboard"
+                    ));
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    self.status = Some(format!("Copied link ({length} characters)"));
+                }
+            }
+            Err(err) => self.report(format!("Could not encode the link: {err}")),
+        }
+    }
+
+    /// Serialize the evaluated model and hand it to the user.
+    fn save_stl(&mut self, binary: bool) {
+        const NAME: &str = "model";
+        let bytes = if binary {
+            match self.csg.to_stl_binary(NAME) {
+                Ok(bytes) => bytes,
+                Err(err) => return self.report(format!("Could not write STL: {err}")),
+            }
+        } else {
+            self.csg.to_stl_ascii(NAME).into_bytes()
+        };
+        match files::save_file(&format!("{NAME}.stl"), &bytes) {
+            Ok(Some(path)) => self.status = Some(format!("Saved {path}")),
+            Ok(None) => {}
+            Err(err) => self.report(format!("Could not save STL: {err}")),
+        }
+    }
+
+    /// Push the state before the edit called `label` onto the undo stack, if
+    /// the scene changed since it was last recorded.
+    fn record_edit(&mut self, label: &str, coalesce: bool) {
+        if self.scene != self.recorded {
+            let before = std::mem::replace(&mut self.recorded, self.scene.clone());
+            self.history.record(label, before, self.recorded_selected, coalesce);
+        }
+        self.recorded_selected = self.selected;
+    }
+
+    fn undo(&mut self) {
+        if let Some(label) = self.history.undo(&mut self.scene, &mut self.selected) {
+            self.status = Some(format!("Undid {label}"));
+            self.restore();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(label) = self.history.redo(&mut self.scene, &mut self.selected) {
+            self.status = Some(format!("Redid {label}"));
+            self.restore();
+        }
+    }
+
+    /// Show a scene put back by undo or redo.
+    fn restore(&mut self) {
+        self.recorded = self.scene.clone();
+        self.recorded_selected = self.selected;
+        self.set_csg(self.scene.evaluate());
+    }
+
+    fn report(&mut self, message: String) {
+        log::warn!("{message}");
+        self.status = Some(message);
+    }
+
+    /// Re-merge vertices, e.g. after the weld tolerance changed.
+    fn reweld(&mut self) {
+        self.welded = WeldedMesh::from_csg(&self.shown, self.options.weld_tolerance);
+        self.bvh = pick::Bvh::new(&self.welded);
+        // picks index the welded mesh
+        self.hovered = None;
+        self.picked = None;
+        self.measure.clear();
+        self.report = None;
+        self.rebuild_edges();
+    }
+
+    /// Re-extract the outline edges, e.g. after the crease angle changed.
+    fn rebuild_edges(&mut self) {
+        self.edges = Arc::new(edges::feature_edges(&self.welded, self.options.crease_angle));
+        self.generation += 1;
+    }
+
+    /// Frame the whole model.
+    fn fit_all(&mut self) {
+        if let Some((center, radius)) = self.sphere {
+            self.frame(center, radius);
+        }
+    }
+
+    /// Frame the selected node, as placed in the world.
+    fn fit_selection(&mut self) {
+        if let Some((center, radius)) = self.selection_sphere() {
+            self.frame(center, radius);
+        }
+    }
+
+    /// Bounding sphere of the selected node, as placed in the world.
+    ///
+    /// Evaluating the node's subtree is slow, so the sphere is kept until the
+    /// selection or the model changes.
+    fn selection_sphere(&mut self) -> Option<(Vec3, f32)> {
+        let id = self.selected?;
+        match self.selection_sphere {
+            Some((cached, sphere)) if cached == id => sphere,
+            _ => {
+                let sphere = self.placed_sphere(id);
+                self.selection_sphere = Some((id, sphere));
+                sphere
+            }
+        }
+    }
+
+    fn placed_sphere(&self, id: NodeId) -> Option<(Vec3, f32)> {
+        let root = &self.scene.root;
+        let local = root.find(id)?.evaluate();
+        // mapping the points needs no inverse, unlike `CSG::transform`, so
+        // an ancestor scaled flat cannot make it panic
+        let matrix = root.parent_matrix(id)?;
+        let placed = local
+            .polygons
+            .iter()
+            .flat_map(|p| &p.vertices)
+            .map(|v| matrix.transform_point(&v.pos));
+        camera::points_sphere(placed)
+    }
+
+    /// Look at the sphere around `center` so it fills `FIT_FILL` of the
+    /// canvas, keeping the current rotation.
+    fn frame(&mut self, center: Vec3, radius: f32) {
+        // a point or a sliver still deserves a sensible view
+        let radius = radius.max(1e-3);
+        self.camera.target = center;
+        self.camera.distance = radius * FIT_DISTANCE;
+        // silhouette radius at the target's depth: under perspective the
+        // sphere's outline is tangent to rays from the eye
+        let extent = match self.camera.projection {
+            Projection::Perspective => {
+                let distance = self.camera.distance;
+                radius * distance / (distance.powi(2) - radius.powi(2)).sqrt()
+            }
+            Projection::Orthographic => radius,
+        };
+        self.camera.zoom = self.zoom_to_fill(extent);
+    }
+
+    /// The zoom at which `extent` model units at the target span `FIT_FILL`
+    /// of the shorter canvas side.
+    fn zoom_to_fill(&self, extent: f32) -> f32 {
+        // solve 0.25 * zoom * EYE_DIST / distance * extent = FIT_FILL / 2
+        2.0 * FIT_FILL * self.camera.distance / (EYE_DIST * extent)
+    }
+
+    /// Smallest and largest zoom, scaled to the model's size.
+    fn zoom_range(&self) -> (f32, f32) {
+        let radius = self.sphere.map_or(1.0, |(_, r)| r.max(1e-3));
+        (
+            self.zoom_to_fill(radius * ZOOM_OUT_LIMIT),
+            self.zoom_to_fill(radius / ZOOM_IN_LIMIT),
+        )
+    }
+
+    /// Scale the view by `factor` about the canvas position `anchor`, so the
+    /// model point under it stays put (exactly at the target's depth).
+    fn zoom_at(&mut self, rect: egui::Rect, anchor: egui::Pos2, factor: f32) {
+        let (min, max) = self.zoom_range();
+        let zoom = (self.camera.zoom * factor).clamp(min, max);
+        // both projections scale the image about the canvas centre; pan the
+        // anchor back to where it was
+        let k = zoom / self.camera.zoom;
+        self.camera.zoom = zoom;
+        self.pan(rect, (anchor - rect.center()) * (1.0 - k));
+    }
+
+    /// Move the image by `delta` pixels by moving the target across the
+    /// view plane.
+    fn pan(&mut self, rect: egui::Rect, delta: egui::Vec2) {
+        let inverse = self.camera.rotation.inverse();
+        let (right, up) = (inverse * Vec3::X, inverse * Vec3::Y);
+        self.camera.target -= (right * delta.x - up * delta.y) / self.pixels_per_unit(rect);
+    }
+
+    /// Turn the view as `orbit` says for a drag from `from` to `to`,
+    /// keeping `pivot` (or else the target) where it is on screen.
+    fn orbit_drag(&mut self, rect: egui::Rect, from: egui::Pos2, to: egui::Pos2) {
+        let projector = cpu::Projector::new(self.view(), self.projection(rect), rect);
+        let pivot = self.pivot.unwrap_or(self.camera.target);
+        let center = projector.project(pivot).0;
+        let radius = rect.width().min(rect.height()) * 0.5;
+        let camera = &mut self.camera;
+        let before = camera.rotation;
+        camera.rotation = self.orbit.drag(before, from, to, center, radius);
+        // the pivot keeps its place in view space
+        camera.target = pivot - camera.rotation.inverse() * (before * (pivot - camera.target));
+    }
+
+    /// Size of a model unit at the target, in pixels.
+    fn pixels_per_unit(&self, rect: egui::Rect) -> f32 {
+        rect.width().min(rect.height()) * 0.25 * self.camera.zoom * EYE_DIST / self.camera.distance
+    }
+
+    /// Model-to-eye transform: the model's rotation about `target`, seen
+    /// from `distance` in front of it.
+    fn view(&self) -> Mat4 {
+        Mat4::from_translation(Vec3::new(0.0, 0.0, -self.camera.distance))
+            * Mat4::from_quat(self.camera.rotation)
+            * Mat4::from_translation(-self.camera.target)
+    }
+
+    /// Eye-to-clip transform for a viewport of the size of `rect`.
+    ///
+    /// Matches the original hand-rolled projection: a model unit at
+    /// `target` spanning `0.25 * zoom` of the shorter canvas side when seen
+    /// from `EYE_DIST`. The orthographic projection keeps that scale at
+    /// every depth.
+    fn projection(&self, rect: egui::Rect) -> Mat4 {
+        let half = rect.size() * 0.5;
+        let size = self.pixels_per_unit(rect);
+        let (near, far) = (self.camera.distance * 0.0025, self.camera.distance * 250.0);
+        match self.camera.projection {
+            Projection::Perspective => {
+                let focal = self.camera.distance * size / half.y;
+                let fov_y = 2.0 * (1.0 / focal).atan();
+                Mat4::perspective_rh_gl(fov_y, half.x / half.y, near, far)
+            }
+            Projection::Orthographic => {
+                let (x, y) = (half.x / size, half.y / size);
+                Mat4::orthographic_rh_gl(-x, x, -y, y, near, far)
+            }
         }
     }
+
+    fn view_projection(&self, rect: egui::Rect) -> Mat4 {
+        self.projection(rect) * self.view()
+    }
 }
 
 impl eframe::App for CsgrsApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &persist::Saved::capture(self));
+        let kept = persist::save_scene(storage, &self.scene);
+        let unsaved = kept.is_err();
+        if let Err(message) = kept
+            && !self.scene_unsaved
+        {
+            self.report(message);
+        }
+        self.scene_unsaved = unsaved;
+    }
+
+    fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
+        if let (Some(gpu), Some(gl)) = (&self.gpu, gl) {
+            gpu.lock().unwrap().destroy(gl);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        for file in files::dropped_files(ctx) {
+            match file {
+                Ok(file) => self.open_file(file),
+                Err(err) => self.report(err),
+            }
+        }
+
+        // a slider or gizmo drag, or typing into a field, is one undo step
+        let gesture = ctx.input(|i| i.pointer.any_down()) || ctx.wants_keyboard_input();
+        if !gesture {
+            self.history.close();
+            let (redo, undo) = ctx.input_mut(|i| {
+                let redo = egui::KeyboardShortcut::new(
+                    egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                    egui::Key::Z,
+                );
+                let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+                // redo first: the undo shortcut also matches with shift held
+                (i.consume_shortcut(&redo), i.consume_shortcut(&undo))
+            });
+            if redo {
+                self.redo();
+            } else if undo {
+                self.undo();
+            }
+            let (fit_all, fit_selection) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Home),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::F),
+                )
+            });
+            if fit_all {
+                self.fit_all();
+            } else if fit_selection {
+                self.fit_selection();
+            }
+        }
+
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.menu_button("File", |ui| {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        if ui.button("Open project…").clicked() {
+                            ui.close_menu();
+                            match files::open_dialog("Project", &[project::EXTENSION]) {
+                                Some(Ok(file)) => self.open_project(file),
+                                Some(Err(err)) => self.report(err),
+                                None => {}
+                            }
+                        }
+                        if ui.button("Open STL…").clicked() {
+                            ui.close_menu();
+                            match files::open_dialog("STL", &["stl", "STL"]) {
+                                Some(Ok(file)) => self.open_stl(file),
+                                Some(Err(err)) => self.report(err),
+                                None => {}
+                            }
+                        }
+                    }
+                    ui.weak("Drop a project or STL file onto the view to open it");
+                    ui.separator();
+                    if ui.button("Save project…").clicked() {
+                        ui.close_menu();
+                        self.save_project();
+                    }
+                    if ui.button("Save STL (binary)…").clicked() {
+                        ui.close_menu();
+                        self.save_stl(true);
+                    }
+                    if ui.button("Save STL (ASCII)…").clicked() {
+                        ui.close_menu();
+                        self.save_stl(false);
+                    }
+                    ui.separator();
+                    if ui.button("Copy link").clicked() {
+                        ui.close_menu();
+                        self.copy_link(ui.ctx());
+                    }
+                });
+                ui.menu_button("Edit", |ui| {
+                    let undo = self.history.undo_label().map(|l| format!("Undo {l}"));
+                    let button = egui::Button::new(undo.as_deref().unwrap_or("Undo"))
+                        .shortcut_text("Ctrl+Z");
+                    if ui.add_enabled(undo.is_some(), button).clicked() {
+                        ui.close_menu();
+                        self.undo();
+                    }
+                    let redo = self.history.redo_label().map(|l| format!("Redo {l}"));
+                    let button = egui::Button::new(redo.as_deref().unwrap_or("Redo"))
+                        .shortcut_text("Ctrl+Shift+Z");
+                    if ui.add_enabled(redo.is_some(), button).clicked() {
+                        ui.close_menu();
+                        self.redo();
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    let button = egui::Button::new("Fit all").shortcut_text("Home");
+                    if ui.add(button).clicked() {
+                        ui.close_menu();
+                        self.fit_all();
+                    }
+                    let button = egui::Button::new("Fit selection").shortcut_text("F");
+                    if ui.add_enabled(self.selected.is_some(), button).clicked() {
+                        ui.close_menu();
+                        self.fit_selection();
+                    }
+                    ui.separator();
+                    for projection in Projection::ALL {
+                        let label = projection.label();
+                        ui.radio_value(&mut self.camera.projection, projection, label);
+                    }
+                    ui.separator();
+                    for orbit in OrbitMode::ALL {
+                        ui.radio_value(&mut self.orbit, orbit, orbit.label());
+                    }
+                    ui.separator();
+                    for view in NamedView::ALL {
+                        if ui.button(view.label()).clicked() {
+                            ui.close_menu();
+                            let now = ui.input(|i| i.time);
+                            let from = self.camera.rotation;
+                            self.transition =
+                                Some(ViewTransition::new(from, view.rotation(), now));
+                        }
+                    }
+                });
+                ui.separator();
+                if ui
+                    .toggle_value(&mut self.measure.active, "📏 Measure")
+                    .changed()
+                {
+                    self.measure.clear();
+                }
+                ui.toggle_value(&mut self.show_inspector, "ℹ Properties");
+                ui.toggle_value(&mut self.check_mesh, "⚠ Check mesh");
+                self.section_controls(ui);
+                ui.separator();
+                for mode in DisplayMode::ALL {
+                    ui.selectable_value(&mut self.options.mode, mode, mode.label());
+                }
+                ui.add_enabled(
+                    self.options.mode == DisplayMode::HiddenLine,
+                    egui::Checkbox::new(&mut self.options.dashed_hidden, "Dashed hidden"),
+                );
+                ui.separator();
+                let crease = ui.add(
+                    egui::Slider::new(&mut self.options.crease_angle, 0.0..=90.0)
+                        .text("Crease angle")
+                        .suffix("°"),
+                );
+                if crease.changed() {
+                    self.rebuild_edges();
+                }
+                let weld = ui.add(
+                    egui::Slider::new(&mut self.options.weld_tolerance, weld::MIN_TOLERANCE..=1.0)
+                        .logarithmic(true)
+                        .text("Weld tolerance"),
+                );
+                if weld.changed() {
+                    self.properties = None;
+                    self.reweld();
+                }
+            });
+        });
+
+        if self.check_mesh && self.report.is_none() {
+            self.report = Some(validate::Report::check(&self.welded));
+        }
+
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(self.status.as_deref().unwrap_or("Ready"));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if let Some(report) = self.report.as_ref().filter(|_| self.check_mesh) {
+                        let summary = egui::RichText::new(report.summary());
+                        if report.is_clean() {
+                            ui.label(summary);
+                        } else {
+                            ui.label(summary.color(ui.visuals().error_fg_color));
+                        }
+                        ui.separator();
+                    }
+                    if let Some(pick) = &self.picked {
+                        ui.label(pick.feature.describe(&self.welded));
+                    }
+                });
+            });
+        });
+
+        egui::SidePanel::left("tree").show(ctx, |ui| {
+            ui.heading("Model");
+            if tree_editor::tree_editor(ui, &mut self.scene, &mut self.selected) {
+                self.set_csg(self.scene.evaluate());
+            }
+            // renames do not change the geometry but are still undoable
+            self.record_edit("model edit", gesture);
+        });
+
+        if self.show_inspector {
+            egui::SidePanel::right("inspector").show(ctx, |ui| {
+                ui.heading("Properties");
+                let properties = self.properties.get_or_insert_with(|| {
+                    inspector::Properties::of(&self.csg, self.options.weld_tolerance)
+                });
+                let units = self.scene.units;
+                inspector::inspector_ui(ui, properties, units, &mut self.density);
+            });
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.set_min_size(ui.available_size());
             let (rect, response) =
-                ui.allocate_exact_size(ui.available_size(), egui::Sense::drag());
+                ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
 
             // ───── Interaction ─────
-            if response.dragged() {
+            if let Some(transition) = self.transition {
+                let (rotation, running) = transition.at(ui.input(|i| i.time));
+                self.camera.rotation = rotation;
+                if running {
+                    ui.ctx().request_repaint();
+                } else {
+                    self.transition = None;
+                }
+            }
+
+            let mut gizmo_shapes = Vec::new();
+            let mut captured = false;
+            if let Some(id) = self.selected.filter(|_| !self.measure.active) {
+                let projector = cpu::Projector::new(self.view(), self.projection(rect), rect);
+                let parent = self.scene.root.parent_matrix(id);
+                if let (Some(parent), Some(node)) = (parent, self.scene.root.find_mut(id)) {
+                    let out = self.gizmo.show(
+                        ui,
+                        &response,
+                        &projector,
+                        self.camera.rotation,
+                        &parent,
+                        &mut node.transform,
+                    );
+                    gizmo_shapes = out.shapes;
+                    captured = out.captured;
+                    if out.changed {
+                        self.set_csg(self.scene.evaluate());
+                        self.record_edit(&self.gizmo.mode.label().to_lowercase(), gesture);
+                    }
+                }
+            }
+
+            let mut section_shapes = Vec::new();
+            if let Some(bounds) = self.bounds().filter(|_| {
+                self.section.enabled && !self.measure.active && !captured
+            }) {
+                let projector = cpu::Projector::new(self.view(), self.projection(rect), rect);
+                let (changed, grabbed) = self.section.handle(
+                    ui,
+                    &response,
+                    &projector,
+                    bounds,
+                    &mut section_shapes,
+                );
+                captured |= grabbed;
+                if changed {
+                    self.recut();
+                }
+            }
+
+            // two fingers → pan, twist → roll; egui also reports the first
+            // finger as a pointer drag, which must not orbit meanwhile
+            ui.input(|i| self.touches.update(&i.events));
+            let multi_touch = ui.input(|i| i.multi_touch()).filter(|_| response.hovered());
+            if let Some(touch) = multi_touch {
+                self.transition = None;
+                self.pan(rect, touch.translation_delta);
+                let rotation = self.camera.rotation;
+                self.camera.rotation = self.orbit.twist(rotation, touch.rotation_delta);
+            }
+
+            let orbiting = !captured && multi_touch.is_none();
+            if orbiting && response.drag_started_by(egui::PointerButton::Primary) {
+                // orbit about the surface under the pointer, else the selection
+                let projector = cpu::Projector::new(self.view(), self.projection(rect), rect);
+                let press = ui.input(|i| i.pointer.press_origin());
+                self.pivot = press
+                    .and_then(|pos| pick::pick(&self.welded, &self.bvh, &projector, pos))
+                    .map(|pick| mesh::to_vec3(&pick.point.coords))
+                    .or_else(|| self.selection_sphere().map(|(center, _)| center));
+            }
+            if !ui.input(|i| i.pointer.primary_down()) {
+                self.pivot = None;
+            }
+
+            if response.dragged() && orbiting {
                 let delta = response.drag_delta();
                 let input = ui.input(|i| i.clone());
-                if input.pointer.primary_down() {
+                let pointer = input.pointer.interact_pos();
+                if let Some(to) = pointer.filter(|_| input.pointer.primary_down()) {
+                    self.transition = None;
                     // left‑drag → rotate
-                    let yaw = delta.x * 0.01;
-                    let pitch = delta.y * 0.01;
-                    self.rotation =
-                        Quat::from_rotation_y(yaw) * Quat::from_rotation_x(pitch) * self.rotation;
+                    self.orbit_drag(rect, to - delta, to);
                 } else if input.pointer.secondary_down() {
                     // right‑drag → pan
-                    self.translation += delta;
+                    self.pan(rect, delta);
                 }
             }
 
-            // scroll → zoom
-            let scroll = ui.input(|i| i.raw_scroll_delta.y);
-            if scroll.abs() > 0.0 {
-                self.zoom = (self.zoom * (1.0 + scroll * 0.001)).clamp(0.2, 5.0);
+            // scroll, pinch or ctrl+scroll → zoom towards the pointer
+            let (scroll, pinch) = ui.input(|i| (i.raw_scroll_delta.y, i.zoom_delta()));
+            let factor = (scroll * 0.001).exp() * pinch;
+            if factor != 1.0 && response.hovered() {
+                let anchor = self
+                    .touches
+                    .center()
+                    .or(response.hover_pos())
+                    .unwrap_or(rect.center());
+                self.zoom_at(rect, anchor, factor);
+            }
+
+            let projector = cpu::Projector::new(self.view(), self.projection(rect), rect);
+            self.hovered = match response.hover_pos() {
+                Some(pos) if !captured && !response.dragged() => {
+                    pick::pick(&self.welded, &self.bvh, &projector, pos)
+                }
+                _ => None,
+            };
+            if response.clicked() && !captured {
+                if !self.measure.active {
+                    self.picked = self.hovered;
+                } else if let Some(hovered) = self.hovered {
+                    self.measure.add(hovered, &self.welded);
+                } else {
+                    self.measure.clear();
+                }
+            }
+            if self.measure.active && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.measure.clear();
             }
 
             // ───── Paint ─────
+            if let Some(gpu) = &self.gpu {
+                let callback = self.paint_callback(rect, ui.ctx().pixels_per_point(), gpu.clone());
+                ui.painter().add(callback);
+            } else {
+                let painter = ui.painter_at(rect);
+                draw_csgrs_cube(&painter, rect, self);
+            }
+
             let painter = ui.painter_at(rect);
-            draw_csgrs_cube(&painter, rect, self);
+            if self.section.enabled {
+                section::paint_hatch(&painter, &projector, &self.section, &self.hatch);
+                painter.extend(section_shapes);
+            }
+            if let Some(report) = self.report.as_ref().filter(|_| self.check_mesh) {
+                report.paint(&painter, &projector, &self.welded);
+            }
+            if let Some(pick) = &self.picked {
+                pick.feature.paint(&painter, &projector, &self.welded, PICKED_COLOR);
+            }
+            let picked = self.picked.map(|p| p.feature);
+            if let Some(pick) = self.hovered.filter(|h| Some(h.feature) != picked) {
+                pick.feature.paint(&painter, &projector, &self.welded, HOVER_COLOR);
+            }
+            painter.extend(gizmo_shapes);
+            if let Some(pivot) = self.pivot.filter(|_| response.dragged()) {
+                let center = projector.project(pivot).0;
+                painter.circle_stroke(center, 4.0, egui::Stroke::new(1.5, HOVER_COLOR));
+            }
+            if self.measure.active {
+                let units = self.scene.units;
+                self.measure.paint(&painter, &projector, &self.welded, units);
+                self.measure_results(ui, rect);
+            } else if self.selected.is_some() {
+                self.gizmo_controls(ui, rect);
+            }
+
+            if files::files_hovered(ui.ctx()) {
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "Drop a project or STL file to open it",
+                    egui::FontId::proportional(24.0),
+                    egui::Color32::WHITE,
+                );
+            }
         });
     }
 }
 
+impl CsgrsApp {
+    /// Section toggle, axis, position and side, in the toolbar.
+    fn section_controls(&mut self, ui: &mut egui::Ui) {
+        let before = self.section;
+        if ui
+            .toggle_value(&mut self.section.enabled, "✂ Section")
+            .changed()
+            && self.section.enabled
+        {
+            self.center_section();
+        }
+        if self.section.enabled {
+            for axis in section::Axis::ALL {
+                if ui
+                    .selectable_value(&mut self.section.axis, axis, axis.label())
+                    .changed()
+                {
+                    self.center_section();
+                }
+            }
+            if let Some((min, max)) = self.bounds() {
+                let i = self.section.axis.index();
+                ui.add(
+                    egui::Slider::new(&mut self.section.offset, min[i]..=max[i]).max_decimals(3),
+                );
+            }
+            ui.checkbox(&mut self.section.flip, "Flip");
+        }
+        if self.section != before {
+            self.recut();
+        }
+    }
+
+    /// Put the section plane through the middle of the model.
+    fn center_section(&mut self) {
+        if let Some((min, max)) = self.bounds() {
+            let i = self.section.axis.index();
+            self.section.offset = (min[i] + max[i]) * 0.5;
+        }
+    }
+
+    /// Gizmo mode and snapping, floating in the canvas corner.
+    fn gizmo_controls(&mut self, ui: &egui::Ui, rect: egui::Rect) {
+        egui::Area::new(egui::Id::new("gizmo controls"))
+            .fixed_pos(rect.left_top() + egui::vec2(8.0, 8.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        for mode in GizmoMode::ALL {
+                            ui.selectable_value(&mut self.gizmo.mode, mode, mode.label());
+                        }
+                    });
+                    let snap = &mut self.gizmo.snap;
+                    ui.checkbox(&mut snap.enabled, "Snap");
+                    ui.add_enabled_ui(snap.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut snap.translate)
+                                    .speed(0.01)
+                                    .clamp_range(0.01..=10.0),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut snap.rotate)
+                                    .speed(1.0)
+                                    .clamp_range(1.0..=90.0)
+                                    .suffix("°"),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut snap.scale)
+                                    .speed(0.01)
+                                    .clamp_range(0.01..=1.0)
+                                    .prefix("×"),
+                            );
+                        });
+                    });
+                });
+            });
+    }
+
+    /// Readout of the current measurements, floating in the canvas corner.
+    fn measure_results(&mut self, ui: &egui::Ui, rect: egui::Rect) {
+        egui::Area::new(egui::Id::new("measure results"))
+            .fixed_pos(rect.left_top() + egui::vec2(8.0, 8.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    if self.measure.picks().is_empty() {
+                        ui.label("Click vertices, edges or faces to measure");
+                        return;
+                    }
+                    egui::Grid::new("measurements").num_columns(2).show(ui, |ui| {
+                        for m in self.measure.measurements() {
+                            ui.label(m.name);
+                            ui.monospace(m.text(self.scene.units));
+                            ui.end_row();
+                        }
+                    });
+                    if ui.button("Clear").clicked() {
+                        self.measure.clear();
+                    }
+                });
+            });
+    }
+
+    /// Draw the model with GL inside `rect`, uploading buffers first if the
+    /// model changed since the last frame.
+    fn paint_callback(
+        &self,
+        rect: egui::Rect,
+        pixels_per_point: f32,
+        gpu: Arc<Mutex<GpuRenderer>>,
+    ) -> egui::PaintCallback {
+        let mesh = GpuMesh {
+            generation: self.generation,
+            mesh: self.mesh.clone(),
+            edges: self.edges.clone(),
+        };
+        let frame = FrameUniforms {
+            view_projection: self.view_projection(rect),
+            rotation: Mat4::from_quat(self.camera.rotation),
+            viewport_px: (rect.size() * pixels_per_point).into(),
+            mode: self.options.mode,
+            dashed_hidden: self.options.dashed_hidden,
+            base_color: BASE_COLOR,
+            edge_color: color_to_vec4(edge_color(self.options.mode)),
+            hidden_color: color_to_vec4(HIDDEN_EDGE_COLOR),
+        };
+        egui::PaintCallback {
+            rect,
+            callback: Arc::new(eframe::egui_glow::CallbackFn::new(move |_info, painter| {
+                let mut gpu = gpu.lock().unwrap();
+                gpu.upload(painter.gl(), &mesh);
+                gpu.paint(painter.gl(), &frame);
+            })),
+        }
+    }
+}
+
+/// CPU fallback: draws through `egui::Painter` when no GL context exists.
 fn draw_csgrs_cube(painter: &egui::Painter, rect: egui::Rect, app: &CsgrsApp) {
-    let stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
-    let size = rect.width().min(rect.height()) * 0.25 * app.zoom;
+    let projector = cpu::Projector::new(app.view(), app.projection(rect), rect);
+    let project = |v: Vec3| projector.project(v);
+    let mode = app.options.mode;
+    let stroke = egui::Stroke::new(if mode == DisplayMode::Wireframe { 2.0 } else { 1.0 }, edge_color(mode));
+
+    if mode == DisplayMode::Wireframe {
+        for (a, b) in app.edges.segments() {
+            painter.line_segment([project(a).0, project(b).0], stroke);
+        }
+        return;
+    }
+
+    let projected: Vec<(egui::Pos2, f32)> = app.mesh.positions.iter().map(|&p| project(p)).collect();
+    let colors = if mode == DisplayMode::HiddenLine {
+        // faces only mask the edges behind them
+        vec![painter.ctx().style().visuals.panel_fill; projected.len()]
+    } else {
+        cpu::shade(&app.mesh, app.camera.rotation, BASE_COLOR)
+    };
+    painter.add(cpu::sorted_triangles(&app.mesh, &projected, &colors));
+    if mode == DisplayMode::Shaded {
+        return;
+    }
 
-	// basic perspective projection
-	let dist = 4.0;
+    let visibility = cpu::edge_visibility(&app.mesh, &projected, &app.edges, &projector, rect);
+    if mode == DisplayMode::HiddenLine && app.options.dashed_hidden {
+        let hidden = egui::Stroke::new(1.0, HIDDEN_EDGE_COLOR);
+        for seg in &visibility.hidden {
+            painter.extend(egui::Shape::dashed_line(seg, hidden, 6.0, 6.0));
+        }
+    }
+    for seg in visibility.visible {
+        painter.line_segment(seg, stroke);
+    }
+}
 
-	let project = |v: Vec3| {
-		let rotated = app.rotation * v;
-		let scale = dist / (dist - rotated.z);
-		let p = egui::vec2(rotated.x * scale, rotated.y * scale);
-		let offset = (egui::vec2(p.x, -p.y) * size) + app.translation;
-		rect.center() + offset
-	};
+/// Colour of hidden edges drawn dashed in hidden-line mode.
+const HIDDEN_EDGE_COLOR: egui::Color32 = egui::Color32::from_gray(110);
 
-    for &(a, b) in &app.edges {
-        painter.line_segment([project(a), project(b)], stroke);
+/// Colour of visible edges: dark over shaded faces, light on the background.
+fn edge_color(mode: DisplayMode) -> egui::Color32 {
+    match mode {
+        DisplayMode::ShadedEdges => egui::Color32::BLACK,
+        _ => egui::Color32::WHITE,
     }
 }
 
+/// Unmultiplied gamma-space colour for a shader uniform, so GL output
+/// matches what the painter draws.
+fn color_to_vec4(c: egui::Color32) -> Vec4 {
+    Vec4::from(c.to_array().map(|x| x as f32 / 255.0))
+}
+
 // ── Web entry‑point ──
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -115,28 +1224,26 @@ pub async fn start() -> Result<(), JsValue> {
     eframe::WebLogger::init(log::LevelFilter::Debug).ok();
     console_error_panic_hook::set_once();
 
-    let web_options = eframe::WebOptions::default();
+    let web_options = eframe::WebOptions {
+        depth_buffer: 24,
+        ..Default::default()
+    };
 
     // The element id must match the <canvas> in your index.html
     eframe::WebRunner::new()
         .start(
             "csgrs_canvas", // canvas id
             web_options,
-            Box::new(|cc| Box::new(CsgrsApp::new(cc))),
+            Box::new(|cc| {
+                let mut app = CsgrsApp::new(cc);
+                // a `#model=…` link opens its model over whatever was saved
+                if share::page_fragment().is_some_and(|f| app.open_link(&f)) {
+                    share::clear_page_fragment();
+                }
+                Box::new(app)
+            }),
         )
         .await?;
 
     Ok(())
 }
-
-// ── Native entry‑point ──
-#[cfg(not(target_arch = "wasm32"))]
-fn main() -> eframe::Result<()> {
-    let options = eframe::NativeOptions::default();
-    eframe::run_native(
-        "csgrs egui wasm example",
-        options,
-        Box::new(|cc| Box::new(CsgrsApp::new(cc))),
-    )
-}
-
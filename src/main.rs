@@ -0,0 +1,26 @@
+// ── Native entry‑point ──
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> eframe::Result<()> {
+    use csgrs_egui_wasm_example::CsgrsApp;
+
+    let options = eframe::NativeOptions {
+        depth_buffer: 24,
+        ..Default::default()
+    };
+    eframe::run_native(
+        "csgrs egui wasm example",
+        options,
+        Box::new(|cc| {
+            let mut app = CsgrsApp::new(cc);
+            // a link copied from the viewer opens its model
+            if let Some(link) = std::env::args().nth(1) {
+                app.open_link(&link);
+            }
+            Box::new(app)
+        }),
+    )
+}
+
+// the web build starts through `start()` in lib.rs instead
+#[cfg(target_arch = "wasm32")]
+fn main() {}
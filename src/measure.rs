@@ -0,0 +1,535 @@
+use csgrs::float_types::{Real, TAU};
+use eframe::egui::{self, Align2, Color32, FontId, Stroke};
+use nalgebra::{Point3, Unit, Vector3};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cpu::Projector;
+use crate::mesh::to_vec3;
+use crate::pick::{Feature, Pick};
+use crate::scene::Units;
+use crate::weld::WeldedMesh;
+
+/// Colour of dimension lines and their labels.
+const DIMENSION_COLOR: Color32 = Color32::from_rgb(255, 220, 90);
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Picks collected in measure mode; clicking a fourth starts over.
+#[derive(Default)]
+pub struct Measure {
+    pub active: bool,
+    picks: Vec<Pick>,
+    /// Derived from `picks` whenever they change.
+    measurements: Vec<Measurement>,
+}
+
+/// One derived quantity and where to draw it.
+pub struct Measurement {
+    pub name: &'static str,
+    value: Real,
+    quantity: Quantity,
+    dimension: Dimension,
+}
+
+enum Quantity {
+    Length,
+    Area,
+    /// In degrees.
+    Angle,
+}
+
+impl Measurement {
+    /// The value as shown, in `units`.
+    pub fn text(&self, units: Units) -> String {
+        match self.quantity {
+            Quantity::Length => units.format(self.value, 1, 4),
+            Quantity::Area => units.format(self.value, 2, 4),
+            Quantity::Angle => format!("{:.2}°", self.value),
+        }
+    }
+}
+
+enum Dimension {
+    /// A dimension line between two points, labelled at its middle.
+    Line(Point3<Real>, Point3<Real>),
+    /// A label at a point.
+    At(Point3<Real>),
+    /// The circle itself, labelled at its centre.
+    Circle(Circle),
+}
+
+impl Measure {
+    pub fn add(&mut self, pick: Pick, welded: &WeldedMesh) {
+        if self.picks.len() == 3 {
+            self.picks.clear();
+        }
+        self.picks.push(pick);
+        self.measurements = measure(&self.picks, welded);
+    }
+
+    pub fn clear(&mut self) {
+        self.picks.clear();
+        self.measurements.clear();
+    }
+
+    pub fn picks(&self) -> &[Pick] {
+        &self.picks
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    /// Mark the picks and draw the dimension annotations.
+    pub fn paint(
+        &self,
+        painter: &egui::Painter,
+        projector: &Projector,
+        welded: &WeldedMesh,
+        units: Units,
+    ) {
+        for pick in &self.picks {
+            pick.feature
+                .paint(painter, projector, welded, DIMENSION_COLOR);
+        }
+        let screen = |p: &Point3<Real>| projector.project(to_vec3(&p.coords)).0;
+        let stroke = Stroke::new(1.5, DIMENSION_COLOR);
+        for m in &self.measurements {
+            let text = format!("{} {}", m.name, m.text(units));
+            let at = match &m.dimension {
+                Dimension::Line(a, b) => {
+                    let (a, b) = (screen(a), screen(b));
+                    painter.line_segment([a, b], stroke);
+                    // end ticks across the line
+                    let across = (b - a).normalized().rot90() * 5.0;
+                    for end in [a, b] {
+                        painter.line_segment([end - across, end + across], stroke);
+                    }
+                    a + (b - a) * 0.5
+                }
+                Dimension::At(p) => screen(p),
+                Dimension::Circle(Circle {
+                    center,
+                    normal,
+                    radius,
+                }) => {
+                    let u = normal.cross(&normal_seed(normal)).normalize();
+                    let v = normal.cross(&u);
+                    let ring = (0..CIRCLE_SEGMENTS)
+                        .map(|k| {
+                            let t = k as Real / CIRCLE_SEGMENTS as Real * TAU;
+                            screen(&(center + (u * t.cos() + v * t.sin()) * *radius))
+                        })
+                        .collect();
+                    painter.add(egui::Shape::closed_line(ring, stroke));
+                    let c = screen(center);
+                    painter.circle_filled(c, 2.5, DIMENSION_COLOR);
+                    c
+                }
+            };
+            let galley = painter.layout_no_wrap(text, FontId::proportional(13.0), DIMENSION_COLOR);
+            let rect = Align2::CENTER_BOTTOM.anchor_size(at - egui::vec2(0.0, 4.0), galley.size());
+            painter.rect_filled(rect.expand(2.0), 3.0, Color32::from_black_alpha(180));
+            painter.galley(rect.min, galley, DIMENSION_COLOR);
+        }
+    }
+}
+
+/// Everything that can be read off `picks`.
+///
+/// A single edge gives its length and a face the area of the flat region it
+/// belongs to, plus the radius of any circular outline of that region. Two
+/// picks give the distance between them — using circle centres for faces
+/// with a circular outline, e.g. the ends of two pins — and the angle between
+/// two faces or edges, or the gap between parallel faces. Three vertices give
+/// the circle through them, e.g. on the rim of a hole.
+fn measure(picks: &[Pick], welded: &WeldedMesh) -> Vec<Measurement> {
+    let edges = welded.edges();
+    let flats: Vec<Option<Flat>> = picks
+        .iter()
+        .map(|pick| match pick.feature {
+            Feature::Face(f) => Some(Flat::around(welded, &edges, f, &pick.point)),
+            _ => None,
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    for (pick, flat) in picks.iter().zip(&flats) {
+        match (pick.feature, flat) {
+            (Feature::Edge([a, b]), _) => {
+                let (a, b) = (vertex(welded, a), vertex(welded, b));
+                out.push(Measurement {
+                    name: "Length",
+                    value: (b - a).norm(),
+                    quantity: Quantity::Length,
+                    dimension: Dimension::Line(a, b),
+                });
+            }
+            (Feature::Face(_), Some(flat)) => {
+                out.push(Measurement {
+                    name: "Area",
+                    value: flat.area,
+                    quantity: Quantity::Area,
+                    dimension: Dimension::At(pick.point),
+                });
+                if let Some(circle) = &flat.circle {
+                    out.push(Measurement {
+                        name: "Radius",
+                        value: circle.radius,
+                        quantity: Quantity::Length,
+                        dimension: Dimension::Circle(*circle),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match picks {
+        [first, second] => {
+            let anchor = |pick: &Pick, flat: &Option<Flat>| match flat {
+                Some(Flat {
+                    circle: Some(circle),
+                    ..
+                }) => circle.center,
+                _ => anchor(welded, pick),
+            };
+            let (a, b) = (anchor(first, &flats[0]), anchor(second, &flats[1]));
+            out.push(Measurement {
+                name: "Distance",
+                value: (b - a).norm(),
+                quantity: Quantity::Length,
+                dimension: Dimension::Line(a, b),
+            });
+            match (first.feature, second.feature) {
+                (Feature::Face(f), Feature::Face(g)) => {
+                    out.push(face_pair(welded, f, g, first, second));
+                }
+                (Feature::Edge(e), Feature::Edge(f)) => {
+                    out.push(edge_angle(welded, e, f));
+                }
+                _ => {}
+            }
+        }
+        [
+            Pick {
+                feature: Feature::Vertex(a),
+                ..
+            },
+            Pick {
+                feature: Feature::Vertex(b),
+                ..
+            },
+            Pick {
+                feature: Feature::Vertex(c),
+                ..
+            },
+        ] => {
+            let points = [*a, *b, *c].map(|v| vertex(welded, v));
+            if let Some(circle) = Circle::through(points) {
+                out.push(Measurement {
+                    name: "Radius",
+                    value: circle.radius,
+                    quantity: Quantity::Length,
+                    dimension: Dimension::Circle(circle),
+                });
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+fn vertex(welded: &WeldedMesh, i: u32) -> Point3<Real> {
+    welded.vertices[i as usize]
+}
+
+/// The point a pick stands for in a distance: the vertex itself, the point
+/// of the edge nearest to where it was clicked, or the clicked point.
+fn anchor(welded: &WeldedMesh, pick: &Pick) -> Point3<Real> {
+    match pick.feature {
+        Feature::Vertex(v) => vertex(welded, v),
+        Feature::Edge([a, b]) => {
+            let (a, b) = (vertex(welded, a), vertex(welded, b));
+            let ab = b - a;
+            let t = if ab.norm_squared() > 0.0 {
+                ((pick.point - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            a + ab * t
+        }
+        Feature::Face(_) => pick.point,
+    }
+}
+
+/// The flat region around a picked face: every polygon reachable through
+/// shared vertices without leaving the face's plane. Primitives come
+/// tessellated (a cylinder cap is a fan of triangles), so this is what reads
+/// as one face.
+///
+/// The weld splits the T-junctions booleans leave where faces meet, so the
+/// region shares whole edges with its neighbours and outlines close on
+/// boolean results as well.
+struct Flat {
+    area: Real,
+    /// The circular outline of the region nearest the pick, if any.
+    circle: Option<Circle>,
+}
+
+impl Flat {
+    fn around(
+        welded: &WeldedMesh,
+        edges: &BTreeMap<[u32; 2], Vec<usize>>,
+        face: usize,
+        near: &Point3<Real>,
+    ) -> Self {
+        const PLANE_TOLERANCE: Real = 1e-6;
+        let normal = welded.normals[face];
+        let origin = vertex(welded, welded.polygons[face][0]);
+        let coplanar = |g: usize| {
+            welded.polygons[g].len() >= 3
+                && welded.normals[g].dot(&normal) > 1.0 - PLANE_TOLERANCE
+                && welded.polygons[g]
+                    .iter()
+                    .all(|&v| (vertex(welded, v) - origin).dot(&normal).abs() < PLANE_TOLERANCE)
+        };
+
+        // neighbours inside a flat face may touch at a single corner of a
+        // fan, so sharing a vertex is enough to join the region
+        let flat: Vec<usize> = (0..welded.polygons.len())
+            .filter(|&g| coplanar(g))
+            .collect();
+        let mut by_vertex: HashMap<u32, Vec<usize>> = HashMap::new();
+        for &g in &flat {
+            for &v in &welded.polygons[g] {
+                by_vertex.entry(v).or_default().push(g);
+            }
+        }
+        let mut region = vec![face];
+        let mut in_region = vec![false; welded.polygons.len()];
+        in_region[face] = true;
+        let mut i = 0;
+        while let Some(&f) = region.get(i) {
+            i += 1;
+            for v in &welded.polygons[f] {
+                for &g in by_vertex.get(v).into_iter().flatten() {
+                    if !in_region[g] {
+                        in_region[g] = true;
+                        region.push(g);
+                    }
+                }
+            }
+        }
+
+        let area = region.iter().map(|&f| welded.polygon_area(f)).sum();
+
+        // outline: region edges shared with a face that leaves the plane
+        let mut next: HashMap<u32, Vec<u32>> = HashMap::new();
+        for &f in &region {
+            for [a, b] in ring_edges(&welded.polygons[f]) {
+                let leaves = edges
+                    .get(&[a, b])
+                    .is_some_and(|users| users.iter().any(|&g| !coplanar(g)));
+                if leaves && !next.get(&a).is_some_and(|n| n.contains(&b)) {
+                    next.entry(a).or_default().push(b);
+                    next.entry(b).or_default().push(a);
+                }
+            }
+        }
+        let circle = outline_loops(next)
+            .iter()
+            .filter_map(|ring| Circle::fit(welded, ring, normal))
+            .min_by(|a, b| {
+                let d = |c: &Circle| ((near - c.center).norm() - c.radius).abs();
+                d(a).total_cmp(&d(b))
+            });
+        Self { area, circle }
+    }
+}
+
+fn ring_edges(ring: &[u32]) -> impl Iterator<Item = [u32; 2]> + '_ {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .filter(|(a, b)| a != b)
+        .map(|(&a, &b)| [a.min(b), a.max(b)])
+}
+
+/// Chain outline edges into closed loops of vertices. Vertices where more
+/// than two outline edges meet end a chain, so only clean loops come out.
+fn outline_loops(mut next: HashMap<u32, Vec<u32>>) -> Vec<Vec<u32>> {
+    let mut loops = Vec::new();
+    let starts: Vec<u32> = next.keys().copied().collect();
+    for start in starts {
+        let mut ring = vec![start];
+        let mut previous = None;
+        let mut current = start;
+        while let Some(neighbours) = next.get(&current).filter(|n| n.len() == 2) {
+            let step = if Some(neighbours[0]) == previous {
+                neighbours[1]
+            } else {
+                neighbours[0]
+            };
+            next.remove(&current);
+            if step == start {
+                loops.push(std::mem::take(&mut ring));
+                break;
+            }
+            previous = Some(current);
+            current = step;
+            ring.push(current);
+        }
+    }
+    loops
+}
+
+#[derive(Clone, Copy)]
+struct Circle {
+    center: Point3<Real>,
+    normal: Unit<Vector3<Real>>,
+    radius: Real,
+}
+
+impl Circle {
+    /// The circle a loop of vertices approximates, if it is regular enough
+    /// to be a tessellated circle.
+    fn fit(welded: &WeldedMesh, ring: &[u32], normal: Vector3<Real>) -> Option<Self> {
+        const MIN_SIDES: usize = 6;
+        const MAX_DEVIATION: Real = 0.01;
+        if ring.len() < MIN_SIDES {
+            return None;
+        }
+        let points: Vec<_> = ring.iter().map(|&i| vertex(welded, i)).collect();
+        let center = Point3::from(
+            points
+                .iter()
+                .fold(Vector3::zeros(), |acc, p| acc + p.coords)
+                / points.len() as Real,
+        );
+        let radii: Vec<Real> = points.iter().map(|p| (p - center).norm()).collect();
+        let radius = radii.iter().sum::<Real>() / radii.len() as Real;
+        let round = radii
+            .iter()
+            .all(|r| (r - radius).abs() <= radius * MAX_DEVIATION);
+        (round && radius > 0.0).then(|| Self {
+            center,
+            normal: Unit::new_normalize(normal),
+            radius,
+        })
+    }
+
+    /// The circle through three points.
+    fn through([p1, p2, p3]: [Point3<Real>; 3]) -> Option<Self> {
+        let (a, b) = (p1 - p3, p2 - p3);
+        let axb = a.cross(&b);
+        let denominator = 2.0 * axb.norm_squared();
+        if denominator < Real::EPSILON {
+            return None;
+        }
+        Some(Self {
+            center: p3 + (b * a.norm_squared() - a * b.norm_squared()).cross(&axb) / denominator,
+            normal: Unit::new_normalize(axb),
+            radius: a.norm() * b.norm() * (a - b).norm() / (2.0 * axb.norm()),
+        })
+    }
+}
+
+/// Angle between two faces, or the gap between them when they are parallel.
+fn face_pair(welded: &WeldedMesh, f: usize, g: usize, a: &Pick, b: &Pick) -> Measurement {
+    const PARALLEL_DEG: Real = 0.01;
+    let (n, m) = (welded.normals[f], welded.normals[g]);
+    let angle = n.angle(&m).to_degrees();
+    if !(PARALLEL_DEG..=180.0 - PARALLEL_DEG).contains(&angle) {
+        let gap = (vertex(welded, welded.polygons[g][0]) - vertex(welded, welded.polygons[f][0]))
+            .dot(&n)
+            .abs();
+        // the gap runs along the normal from the first clicked point
+        let foot = a.point + n * (b.point - a.point).dot(&n);
+        return Measurement {
+            name: "Gap",
+            value: gap,
+            quantity: Quantity::Length,
+            dimension: Dimension::Line(a.point, foot),
+        };
+    }
+    Measurement {
+        name: "Angle",
+        value: angle,
+        quantity: Quantity::Angle,
+        dimension: Dimension::At(Point3::from((a.point.coords + b.point.coords) * 0.5)),
+    }
+}
+
+/// Angle between two edges: at their shared vertex if they meet, otherwise
+/// between their lines.
+fn edge_angle(welded: &WeldedMesh, e: [u32; 2], f: [u32; 2]) -> Measurement {
+    let shared = e.iter().find(|v| f.contains(v)).copied();
+    let (d1, d2, at) = match shared {
+        Some(s) => {
+            let other = |edge: [u32; 2]| if edge[0] == s { edge[1] } else { edge[0] };
+            let p = vertex(welded, s);
+            (
+                vertex(welded, other(e)) - p,
+                vertex(welded, other(f)) - p,
+                p,
+            )
+        }
+        None => {
+            let d1 = vertex(welded, e[1]) - vertex(welded, e[0]);
+            let mut d2 = vertex(welded, f[1]) - vertex(welded, f[0]);
+            if d1.dot(&d2) < 0.0 {
+                d2 = -d2;
+            }
+            let mid = (vertex(welded, e[0]).coords + vertex(welded, f[0]).coords) * 0.5;
+            (d1, d2, Point3::from(mid))
+        }
+    };
+    Measurement {
+        name: "Angle",
+        value: d1.angle(&d2).to_degrees(),
+        quantity: Quantity::Angle,
+        dimension: Dimension::At(at),
+    }
+}
+
+/// Any vector not parallel to `normal`, to build a basis in its plane.
+fn normal_seed(normal: &Unit<Vector3<Real>>) -> Vector3<Real> {
+    if normal.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use csgrs::csg::CSG;
+    use nalgebra::Point3;
+
+    use super::Flat;
+    use crate::scene::difference;
+    use crate::weld::WeldedMesh;
+
+    #[test]
+    fn hole_outline_is_found_on_a_boolean_result() {
+        let block = CSG::cube(4.0, 4.0, 2.0, None).center();
+        let pin = CSG::cylinder(1.0, 4.0, 32, None).center();
+        let welded = WeldedMesh::from_csg(&difference(&block, &pin), 1e-5);
+        let top = (0..welded.polygons.len())
+            .find(|&f| {
+                welded.normals[f].z > 0.99
+                    && welded.polygons[f]
+                        .iter()
+                        .all(|&v| welded.vertices[v as usize].z > 0.99)
+            })
+            .unwrap();
+        let near = Point3::new(1.0, 0.0, 1.0);
+        let flat = Flat::around(&welded, &welded.edges(), top, &near);
+        let circle = flat.circle.expect("no circular outline");
+        assert!(
+            (circle.radius - 1.0).abs() < 1e-6,
+            "radius {}",
+            circle.radius
+        );
+        assert!(circle.center.coords.xy().norm() < 1e-6);
+    }
+}
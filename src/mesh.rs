@@ -0,0 +1,47 @@
+use csgrs::csg::CSG;
+use glam::Vec3;
+use std::fmt::Debug;
+
+/// Triangulated copy of a `CSG`, flattened into plain `f32` buffers that the
+/// renderer can consume directly.
+#[derive(Clone, Debug, Default)]
+pub struct TriMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    /// Three indices per triangle into `positions` / `normals`.
+    pub indices: Vec<u32>,
+}
+
+impl TriMesh {
+    /// Tessellate every polygon of `csg` and keep each vertex's own normal.
+    pub fn from_csg<S: Clone + Debug + Send + Sync>(csg: &CSG<S>) -> Self {
+        let mut mesh = Self::default();
+        for poly in &csg.polygons {
+            for tri in poly.tessellate() {
+                for v in &tri {
+                    mesh.indices.push(mesh.positions.len() as u32);
+                    mesh.positions.push(to_vec3(&v.pos.coords));
+                    let n = to_vec3(&v.normal).normalize_or_zero();
+                    // fall back to the polygon plane if the vertex normal is degenerate
+                    let n = if n == Vec3::ZERO {
+                        to_vec3(&poly.plane.normal()).normalize_or_zero()
+                    } else {
+                        n
+                    };
+                    mesh.normals.push(n);
+                }
+            }
+        }
+        mesh
+    }
+
+    /// Iterate triangles as index triples.
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]])
+    }
+}
+
+/// Narrow a csgrs (`f64`) vector to the renderer's `f32` representation.
+pub fn to_vec3(v: &nalgebra::Vector3<csgrs::float_types::Real>) -> Vec3 {
+    Vec3::new(v.x as f32, v.y as f32, v.z as f32)
+}
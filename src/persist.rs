@@ -0,0 +1,109 @@
+use csgrs::float_types::Real;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::{Camera, OrbitMode};
+use crate::scene::{NodeId, Scene};
+use crate::section::Section;
+use crate::{CsgrsApp, RenderOptions};
+
+/// Storage key of the scene, kept apart from [`Saved`] so a model too big
+/// to store cannot take the settings down with it.
+const SCENE_KEY: &str = "scene";
+
+/// Largest scene kept between visits, as stored text. Browsers allow about
+/// 5 MB of local storage per site, shared with egui's own state; imported
+/// meshes are what gets near it.
+#[cfg(target_arch = "wasm32")]
+const MAX_SCENE_BYTES: usize = 2 << 20;
+/// Natively the app data directory has no quota worth the name.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_SCENE_BYTES: usize = 1 << 30;
+
+/// What survives a restart besides the scene: the view and which panels are
+/// open. Kept by eframe in local storage on the web and in the app data
+/// directory natively.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Saved {
+    camera: Camera,
+    orbit: OrbitMode,
+    options: RenderOptions,
+    section: Section,
+    show_inspector: bool,
+    check_mesh: bool,
+    density: Real,
+    selected: Option<NodeId>,
+}
+
+impl Default for Saved {
+    fn default() -> Self {
+        Self {
+            camera: Camera::default(),
+            orbit: OrbitMode::default(),
+            options: RenderOptions::default(),
+            section: Section::default(),
+            show_inspector: false,
+            check_mesh: false,
+            density: 1.0,
+            selected: None,
+        }
+    }
+}
+
+impl Saved {
+    pub fn capture(app: &CsgrsApp) -> Self {
+        Self {
+            camera: app.camera,
+            orbit: app.orbit,
+            options: app.options,
+            section: app.section,
+            show_inspector: app.show_inspector,
+            check_mesh: app.check_mesh,
+            density: app.density,
+            selected: app.selected,
+        }
+    }
+
+    /// Put the saved state back, with `scene` if one was kept; the caller
+    /// re-evaluates it.
+    pub fn restore(self, app: &mut CsgrsApp, scene: Option<Scene>) {
+        app.camera = self.camera;
+        app.orbit = self.orbit;
+        app.options = self.options;
+        app.section = self.section;
+        app.show_inspector = self.show_inspector;
+        app.check_mesh = self.check_mesh;
+        app.density = self.density;
+        if let Some(scene) = scene {
+            app.recorded = scene.clone();
+            app.scene = scene;
+        }
+        app.selected = self
+            .selected
+            .filter(|&id| app.scene.root.find(id).is_some());
+        app.recorded_selected = app.selected;
+    }
+}
+
+/// Store `scene` for the next visit. A scene over the size limit is left
+/// out, along with any older one; one that cannot be written leaves the
+/// older one in place. Either way the error says why, for the status bar.
+pub fn save_scene(storage: &mut dyn eframe::Storage, scene: &Scene) -> Result<(), String> {
+    let text = ron::to_string(scene)
+        .map_err(|err| format!("Could not keep the model for the next visit: {err}"))?;
+    if text.len() > MAX_SCENE_BYTES {
+        storage.set_string(SCENE_KEY, String::new());
+        return Err(format!(
+            "The model ({:.1} MB) is too large to keep for the next visit; \
+             save it as a project instead",
+            text.len() as f64 / 1e6
+        ));
+    }
+    storage.set_string(SCENE_KEY, text);
+    Ok(())
+}
+
+/// The scene kept by [`save_scene`], if any.
+pub fn load_scene(storage: &dyn eframe::Storage) -> Option<Scene> {
+    ron::from_str(&storage.get_string(SCENE_KEY)?).ok()
+}
@@ -0,0 +1,301 @@
+use csgrs::float_types::Real;
+use eframe::egui::{self, Color32, Pos2, Stroke};
+use nalgebra::{Point3, Vector3};
+
+use crate::cpu::Projector;
+use crate::mesh::to_vec3;
+use crate::weld::WeldedMesh;
+
+/// Part of the welded model, by index into [`WeldedMesh`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    Vertex(u32),
+    /// Undirected edge, lower index first.
+    Edge([u32; 2]),
+    /// Index into `polygons`, which follows the order of the `CSG` polygons.
+    Face(usize),
+}
+
+/// What lies under the pointer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pick {
+    pub feature: Feature,
+    /// Where the ray met the surface.
+    pub point: Point3<Real>,
+}
+
+/// A vertex within this many pixels of the pointer wins over its edges and face.
+const VERTEX_PX: f32 = 8.0;
+/// An edge within this many pixels of the pointer wins over its face.
+const EDGE_PX: f32 = 5.0;
+
+/// Polygons per BVH leaf.
+const LEAF_SIZE: usize = 4;
+
+/// Bounding volume hierarchy over the polygons of a [`WeldedMesh`], so a
+/// pick only tests the few polygons near the ray.
+#[derive(Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// Polygon indices, grouped so every node covers a contiguous run.
+    order: Vec<usize>,
+}
+
+struct BvhNode {
+    min: Point3<Real>,
+    max: Point3<Real>,
+    /// `order[start..end]` for a leaf.
+    start: usize,
+    end: usize,
+    /// Indices of the two halves; `None` for a leaf.
+    children: Option<[usize; 2]>,
+}
+
+impl Bvh {
+    pub fn new(welded: &WeldedMesh) -> Self {
+        let bounds: Vec<(Point3<Real>, Point3<Real>)> = welded
+            .polygons
+            .iter()
+            .map(|ring| {
+                let mut points = ring.iter().map(|&i| welded.vertices[i as usize]);
+                let first = points.next().unwrap_or_else(Point3::origin);
+                points.fold((first, first), |(lo, hi), p| (lo.inf(&p), hi.sup(&p)))
+            })
+            .collect();
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            order: (0..bounds.len())
+                .filter(|&i| welded.polygons[i].len() >= 3)
+                .collect(),
+        };
+        if !bvh.order.is_empty() {
+            bvh.build(&bounds, 0, bvh.order.len());
+        }
+        bvh
+    }
+
+    /// Add the node covering `order[start..end]`, and its subtree; returns
+    /// its index.
+    fn build(
+        &mut self,
+        bounds: &[(Point3<Real>, Point3<Real>)],
+        start: usize,
+        end: usize,
+    ) -> usize {
+        let (min, max) = self.order[start..end]
+            .iter()
+            .map(|&i| bounds[i])
+            .reduce(|(lo, hi), (a, b)| (lo.inf(&a), hi.sup(&b)))
+            .unwrap_or((Point3::origin(), Point3::origin()));
+        let index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            min,
+            max,
+            start,
+            end,
+            children: None,
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        // halve along the longest side, by polygon centre
+        let axis = (max - min).imax();
+        let center = |i: usize| bounds[i].0[axis] + bounds[i].1[axis];
+        let mid = (start + end) / 2;
+        self.order[start..end]
+            .select_nth_unstable_by(mid - start, |&a, &b| center(a).total_cmp(&center(b)));
+        let first = self.build(bounds, start, mid);
+        let second = self.build(bounds, mid, end);
+        self.nodes[index].children = Some([first, second]);
+        index
+    }
+
+    /// Nearest polygon hit by the ray, with the ray parameter of the hit.
+    fn cast(
+        &self,
+        welded: &WeldedMesh,
+        origin: &Point3<Real>,
+        dir: &Vector3<Real>,
+    ) -> Option<(usize, Real)> {
+        let mut best: Option<(usize, Real)> = None;
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            let Some(near) = slab(origin, dir, &node.min, &node.max) else {
+                continue;
+            };
+            if best.is_some_and(|(_, t)| near > t) {
+                continue;
+            }
+            if let Some(children) = node.children {
+                stack.extend(children);
+                continue;
+            }
+            for &i in &self.order[node.start..node.end] {
+                let ring = &welded.polygons[i];
+                let p = |k: usize| welded.vertices[ring[k] as usize];
+                let hit = (1..ring.len() - 1)
+                    .filter_map(|k| intersect(origin, dir, [p(0), p(k), p(k + 1)]))
+                    .min_by(Real::total_cmp);
+                if let Some(t) = hit.filter(|&t| best.is_none_or(|(_, b)| t < b)) {
+                    best = Some((i, t));
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Ray parameter where the ray enters the box, if it meets it at all.
+fn slab(
+    origin: &Point3<Real>,
+    dir: &Vector3<Real>,
+    min: &Point3<Real>,
+    max: &Point3<Real>,
+) -> Option<Real> {
+    let (mut near, mut far) = (0.0, Real::INFINITY);
+    for axis in 0..3 {
+        let inv = 1.0 / dir[axis];
+        let a = (min[axis] - origin[axis]) * inv;
+        let b = (max[axis] - origin[axis]) * inv;
+        // a ray parallel to the slab gives NaN when it starts on a face;
+        // `max`/`min` then keep the other bound
+        near = a.min(b).max(near);
+        far = a.max(b).min(far);
+    }
+    (near <= far).then_some(near)
+}
+
+/// Cast a ray through `pos` and return the nearest face it hits, or one of
+/// that face's vertices or edges when the pointer is close to them on screen.
+pub fn pick(welded: &WeldedMesh, bvh: &Bvh, projector: &Projector, pos: Pos2) -> Option<Pick> {
+    let (origin, dir) = projector.ray(pos);
+    let origin = Point3::new(origin.x, origin.y, origin.z).cast::<Real>();
+    let dir = Vector3::new(dir.x, dir.y, dir.z).cast::<Real>();
+
+    let (face, t) = bvh.cast(welded, &origin, &dir)?;
+    let point = origin + dir * t;
+
+    let ring = &welded.polygons[face];
+    let screen = |i: u32| {
+        projector
+            .project(to_vec3(&welded.vertices[i as usize].coords))
+            .0
+    };
+    let vertex = ring
+        .iter()
+        .map(|&i| (i, screen(i).distance(pos)))
+        .filter(|(_, d)| *d < VERTEX_PX)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    let edge = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(&a, &b)| {
+            (
+                [a.min(b), a.max(b)],
+                segment_distance(pos, screen(a), screen(b)),
+            )
+        })
+        .filter(|(_, d)| *d < EDGE_PX)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+    let feature = match (vertex, edge) {
+        (Some((v, _)), _) => Feature::Vertex(v),
+        (None, Some((e, _))) => Feature::Edge(e),
+        (None, None) => Feature::Face(face),
+    };
+    Some(Pick { feature, point })
+}
+
+/// Ray parameter of the hit with a triangle seen from either side
+/// (Möller–Trumbore).
+fn intersect(
+    origin: &Point3<Real>,
+    dir: &Vector3<Real>,
+    [a, b, c]: [Point3<Real>; 3],
+) -> Option<Real> {
+    let (e1, e2) = (b - a, c - a);
+    let p = dir.cross(&e2);
+    let det = e1.dot(&p);
+    if det.abs() < Real::EPSILON {
+        return None;
+    }
+    let s = origin - a;
+    let u = s.dot(&p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&e1);
+    let v = dir.dot(&q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = e2.dot(&q) / det;
+    (t >= 0.0).then_some(t)
+}
+
+/// Distance on screen from `p` to the segment from `a` to `b`.
+pub(crate) fn segment_distance(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 {
+        ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+impl Feature {
+    /// Short description for the status bar.
+    pub fn describe(&self, welded: &WeldedMesh) -> String {
+        match *self {
+            Self::Vertex(v) => {
+                let p = welded.vertices[v as usize];
+                format!("Vertex {v} at ({:.3}, {:.3}, {:.3})", p.x, p.y, p.z)
+            }
+            Self::Edge([a, b]) => format!("Edge {a}–{b}"),
+            Self::Face(f) => format!("Face {f} ({} sides)", welded.polygons[f].len()),
+        }
+    }
+
+    /// Outline `self` over the model.
+    pub fn paint(
+        &self,
+        painter: &egui::Painter,
+        projector: &Projector,
+        welded: &WeldedMesh,
+        color: Color32,
+    ) {
+        let screen = |i: u32| {
+            projector
+                .project(to_vec3(&welded.vertices[i as usize].coords))
+                .0
+        };
+        match *self {
+            Self::Vertex(v) => {
+                painter.circle(screen(v), 5.0, color, Stroke::new(1.5, Color32::BLACK));
+            }
+            Self::Edge([a, b]) => {
+                painter.line_segment([screen(a), screen(b)], Stroke::new(3.0, color));
+            }
+            Self::Face(f) => {
+                let ring: Vec<Pos2> = welded.polygons[f].iter().map(|&i| screen(i)).collect();
+                let mut mesh = egui::Mesh::default();
+                let fill = color.gamma_multiply(0.35);
+                for &p in &ring {
+                    mesh.colored_vertex(p, fill);
+                }
+                for k in 1..ring.len().saturating_sub(1) as u32 {
+                    mesh.add_triangle(0, k, k + 1);
+                }
+                painter.add(mesh);
+                painter.add(egui::Shape::closed_line(ring, Stroke::new(2.0, color)));
+            }
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use csgrs::csg::CSG;
+use csgrs::float_types::Real;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Sizes the sliders offer.
+const LENGTH_RANGE: RangeInclusive<Real> = 0.05..=10.0;
+/// Most segments or stacks the sliders offer.
+const MAX_COUNT: usize = 128;
+
+/// A csgrs solid constructor together with its parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Primitive {
+    Cube {
+        width: Real,
+        length: Real,
+        height: Real,
+    },
+    Sphere {
+        radius: Real,
+        segments: usize,
+        stacks: usize,
+    },
+    Cylinder {
+        radius: Real,
+        height: Real,
+        segments: usize,
+    },
+    Frustum {
+        radius1: Real,
+        radius2: Real,
+        height: Real,
+        segments: usize,
+    },
+    Torus {
+        major_r: Real,
+        minor_r: Real,
+        segments_major: usize,
+        segments_minor: usize,
+    },
+    Ellipsoid {
+        rx: Real,
+        ry: Real,
+        rz: Real,
+        segments: usize,
+        stacks: usize,
+    },
+    Octahedron {
+        radius: Real,
+    },
+    Icosahedron {
+        radius: Real,
+    },
+    /// Square pyramid, built with `CSG::polyhedron`.
+    Pyramid {
+        width: Real,
+        height: Real,
+    },
+}
+
+impl Primitive {
+    /// One of each kind, with reasonable default parameters.
+    pub fn library() -> [Self; 9] {
+        [
+            Self::Cube {
+                width: 2.0,
+                length: 2.0,
+                height: 2.0,
+            },
+            Self::Sphere {
+                radius: 1.0,
+                segments: 24,
+                stacks: 12,
+            },
+            Self::Cylinder {
+                radius: 1.0,
+                height: 2.0,
+                segments: 24,
+            },
+            Self::Frustum {
+                radius1: 1.0,
+                radius2: 0.5,
+                height: 2.0,
+                segments: 24,
+            },
+            Self::Torus {
+                major_r: 1.5,
+                minor_r: 0.5,
+                segments_major: 32,
+                segments_minor: 16,
+            },
+            Self::Ellipsoid {
+                rx: 1.5,
+                ry: 1.0,
+                rz: 0.75,
+                segments: 24,
+                stacks: 12,
+            },
+            Self::Octahedron { radius: 1.5 },
+            Self::Icosahedron { radius: 2.0 },
+            Self::Pyramid {
+                width: 2.0,
+                height: 1.5,
+            },
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cube { .. } => "Cube",
+            Self::Sphere { .. } => "Sphere",
+            Self::Cylinder { .. } => "Cylinder",
+            Self::Frustum { .. } => "Frustum",
+            Self::Torus { .. } => "Torus",
+            Self::Ellipsoid { .. } => "Ellipsoid",
+            Self::Octahedron { .. } => "Octahedron",
+            Self::Icosahedron { .. } => "Icosahedron",
+            Self::Pyramid { .. } => "Pyramid",
+        }
+    }
+
+    /// Run the constructor; the result is centred on the origin.
+    pub fn build(&self) -> CSG<()> {
+        let csg = match *self {
+            Self::Cube {
+                width,
+                length,
+                height,
+            } => CSG::cube(width, length, height, None),
+            Self::Sphere {
+                radius,
+                segments,
+                stacks,
+            } => CSG::sphere(radius, segments, stacks, None),
+            Self::Cylinder {
+                radius,
+                height,
+                segments,
+            } => CSG::cylinder(radius, height, segments, None),
+            Self::Frustum {
+                radius1,
+                radius2,
+                height,
+                segments,
+            } => CSG::frustum(radius1, radius2, height, segments, None),
+            Self::Torus {
+                major_r,
+                minor_r,
+                segments_major,
+                segments_minor,
+            } => CSG::torus(major_r, minor_r, segments_major, segments_minor, None),
+            Self::Ellipsoid {
+                rx,
+                ry,
+                rz,
+                segments,
+                stacks,
+            } => CSG::ellipsoid(rx, ry, rz, segments, stacks, None),
+            Self::Octahedron { radius } => CSG::octahedron(radius, None),
+            Self::Icosahedron { radius } => CSG::icosahedron(radius, None),
+            Self::Pyramid { width, height } => {
+                let h = width / 2.0;
+                let points = [
+                    [-h, -h, 0.0],
+                    [h, -h, 0.0],
+                    [h, h, 0.0],
+                    [-h, h, 0.0],
+                    [0.0, 0.0, height],
+                ];
+                let faces = [
+                    vec![0, 3, 2, 1],
+                    vec![0, 1, 4],
+                    vec![1, 2, 4],
+                    vec![2, 3, 4],
+                    vec![3, 0, 4],
+                ];
+                CSG::polyhedron(&points, &faces, None)
+            }
+        };
+        csg.center()
+    }
+
+    /// Bring parameters read from a file or link into the sliders' ranges:
+    /// csgrs panics on some sizes and a huge segment count stalls it. Fails
+    /// on a size that is not a finite number.
+    pub fn sanitize(&mut self) -> Result<(), String> {
+        let label = self.label();
+        let (lengths, counts) = self.params_mut();
+        for length in lengths {
+            if !length.is_finite() {
+                return Err(format!("a {} size is not a finite number", label.to_lowercase()));
+            }
+            *length = length.clamp(*LENGTH_RANGE.start(), *LENGTH_RANGE.end());
+        }
+        for (count, min) in counts {
+            *count = (*count).clamp(min, MAX_COUNT);
+        }
+        Ok(())
+    }
+
+    /// The sizes, and the counts with their smallest value.
+    fn params_mut(&mut self) -> (Vec<&mut Real>, Vec<(&mut usize, usize)>) {
+        match self {
+            Self::Cube {
+                width,
+                length,
+                height,
+            } => (vec![width, length, height], vec![]),
+            Self::Sphere {
+                radius,
+                segments,
+                stacks,
+            } => (vec![radius], vec![(segments, 3), (stacks, 2)]),
+            Self::Cylinder {
+                radius,
+                height,
+                segments,
+            } => (vec![radius, height], vec![(segments, 3)]),
+            Self::Frustum {
+                radius1,
+                radius2,
+                height,
+                segments,
+            } => (vec![radius1, radius2, height], vec![(segments, 3)]),
+            Self::Torus {
+                major_r,
+                minor_r,
+                segments_major,
+                segments_minor,
+            } => (
+                vec![major_r, minor_r],
+                vec![(segments_major, 3), (segments_minor, 3)],
+            ),
+            Self::Ellipsoid {
+                rx,
+                ry,
+                rz,
+                segments,
+                stacks,
+            } => (vec![rx, ry, rz], vec![(segments, 3), (stacks, 2)]),
+            Self::Octahedron { radius } | Self::Icosahedron { radius } => (vec![radius], vec![]),
+            Self::Pyramid { width, height } => (vec![width, height], vec![]),
+        }
+    }
+
+    /// Sliders for every parameter; returns whether any of them moved.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        match self {
+            Self::Cube {
+                width,
+                length,
+                height,
+            } => {
+                changed |= length_slider(ui, width, "Width");
+                changed |= length_slider(ui, length, "Length");
+                changed |= length_slider(ui, height, "Height");
+            }
+            Self::Sphere {
+                radius,
+                segments,
+                stacks,
+            } => {
+                changed |= length_slider(ui, radius, "Radius");
+                changed |= count_slider(ui, segments, 3, "Segments");
+                changed |= count_slider(ui, stacks, 2, "Stacks");
+            }
+            Self::Cylinder {
+                radius,
+                height,
+                segments,
+            } => {
+                changed |= length_slider(ui, radius, "Radius");
+                changed |= length_slider(ui, height, "Height");
+                changed |= count_slider(ui, segments, 3, "Segments");
+            }
+            Self::Frustum {
+                radius1,
+                radius2,
+                height,
+                segments,
+            } => {
+                changed |= length_slider(ui, radius1, "Bottom radius");
+                changed |= length_slider(ui, radius2, "Top radius");
+                changed |= length_slider(ui, height, "Height");
+                changed |= count_slider(ui, segments, 3, "Segments");
+            }
+            Self::Torus {
+                major_r,
+                minor_r,
+                segments_major,
+                segments_minor,
+            } => {
+                changed |= length_slider(ui, major_r, "Major radius");
+                changed |= length_slider(ui, minor_r, "Minor radius");
+                changed |= count_slider(ui, segments_major, 3, "Major segments");
+                changed |= count_slider(ui, segments_minor, 3, "Minor segments");
+            }
+            Self::Ellipsoid {
+                rx,
+                ry,
+                rz,
+                segments,
+                stacks,
+            } => {
+                changed |= length_slider(ui, rx, "Radius X");
+                changed |= length_slider(ui, ry, "Radius Y");
+                changed |= length_slider(ui, rz, "Radius Z");
+                changed |= count_slider(ui, segments, 3, "Segments");
+                changed |= count_slider(ui, stacks, 2, "Stacks");
+            }
+            Self::Octahedron { radius } | Self::Icosahedron { radius } => {
+                changed |= length_slider(ui, radius, "Radius");
+            }
+            Self::Pyramid { width, height } => {
+                changed |= length_slider(ui, width, "Width");
+                changed |= length_slider(ui, height, "Height");
+            }
+        }
+        changed
+    }
+}
+
+fn length_slider(ui: &mut egui::Ui, value: &mut Real, label: &str) -> bool {
+    ui.add(egui::Slider::new(value, LENGTH_RANGE).text(label))
+        .changed()
+}
+
+fn count_slider(ui: &mut egui::Ui, value: &mut usize, min: usize, label: &str) -> bool {
+    ui.add(egui::Slider::new(value, min..=MAX_COUNT).text(label))
+        .changed()
+}
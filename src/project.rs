@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::camera::Camera;
+use crate::scene::Scene;
+
+/// Extension of project files.
+pub const EXTENSION: &str = "csgproj";
+
+/// Rewrites a parsed file from one schema version to the next.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Upgrades from each older schema, oldest first: `MIGRATIONS[i]` rewrites
+/// a version `i + 1` file into version `i + 2`.
+///
+/// Fields added with a `#[serde(default)]` need no step; renames, moves
+/// and changed meanings do. A step edits the parsed JSON, so it never needs
+/// the old Rust types.
+const MIGRATIONS: &[Migration] = &[];
+
+/// Schema version written by this build.
+pub const VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// A saved modelling session: the construction tree with each object's
+/// name, colour tag and metadata, the scene units and the view.
+///
+/// Stored as JSON. Every float is written with enough digits to read back
+/// the same bits and imported meshes are embedded as binary STL, so saving
+/// and loading changes nothing.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    version: u32,
+    pub scene: Scene,
+    pub camera: Camera,
+}
+
+impl Project {
+    pub fn new(scene: Scene, camera: Camera) -> Self {
+        Self {
+            version: VERSION,
+            scene,
+            camera,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// Parse a project file of this or any older schema version, with values
+    /// no editor could produce refused or brought into range.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        Self::parse(text, 1, MIGRATIONS)
+    }
+
+    /// [`Self::from_json`] for a schema history whose oldest version is
+    /// `oldest` and where `migrations[i]` upgrades version `oldest + i`.
+    fn parse(text: &str, oldest: u32, migrations: &[Migration]) -> Result<Self, String> {
+        let latest = oldest + migrations.len() as u32;
+        let mut file: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let version = file
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or("not a project file (no schema version)")?;
+        if version < oldest as u64 || version > latest as u64 {
+            return Err(format!(
+                "schema version {version} is not supported; this build reads {oldest} to {latest}"
+            ));
+        }
+        let pending = &migrations[(version - oldest as u64) as usize..];
+        for (from, step) in (version..).zip(pending) {
+            step(&mut file).map_err(|e| format!("upgrading from version {from}: {e}"))?;
+        }
+        file["version"] = latest.into();
+        let mut project: Self = serde_json::from_value(file).map_err(|e| e.to_string())?;
+        project.scene.sanitize()?;
+        project.camera.sanitize()?;
+        Ok(project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{ImportedMesh, NodeKind};
+    use csgrs::csg::CSG;
+
+    fn project() -> Project {
+        let stl = CSG::<()>::cube(1.0, 2.0, 3.0, None)
+            .translate(0.1, 0.2, 0.3)
+            .to_stl_binary("part")
+            .unwrap();
+        let mesh = ImportedMesh::from_stl("part.stl", &stl).unwrap();
+        let mut scene = Scene::with_leaf(NodeKind::Mesh(mesh));
+        let node = scene.root.children[0].id;
+        scene.root.find_mut(node).unwrap().transform.rotation = [10.0, 20.0, 1.0 / 3.0];
+        let camera = Camera {
+            zoom: 0.1,
+            ..Camera::default()
+        };
+        Project::new(scene, camera)
+    }
+
+    #[test]
+    fn round_trip_is_exact() {
+        let json = project().to_json().unwrap();
+        let read = Project::from_json(&json).unwrap();
+        assert_eq!(read.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn migrates_old_files() {
+        // a made-up version 0 that called the scene "model"
+        let rename: Migration = |file| {
+            let object = file.as_object_mut().ok_or("not an object")?;
+            let model = object.remove("model").ok_or("no model")?;
+            object.insert("scene".into(), model);
+            Ok(())
+        };
+        let mut file: Value = serde_json::from_str(&project().to_json().unwrap()).unwrap();
+        let object = file.as_object_mut().unwrap();
+        let scene = object.remove("scene").unwrap();
+        object.insert("model".into(), scene);
+        object.insert("version".into(), 0.into());
+        let old = serde_json::to_string(&file).unwrap();
+
+        let read = Project::parse(&old, 0, &[rename]).unwrap();
+        assert_eq!(read.version, 1);
+        assert_eq!(read.to_json().unwrap(), project().to_json().unwrap());
+        assert!(Project::from_json(&old).is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use csgrs::csg::CSG;
+use csgrs::float_types::Real;
+use csgrs::float_types::parry3d::bounding_volume::BoundingVolume;
+use nalgebra::{Matrix4, Rotation3, Translation3, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::primitives::Primitive;
+
+/// Stable handle of a node, unique within its [`Scene`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeId(pub u64);
+
+/// Scale, then rotate (degrees about X, Y, Z), then translate.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub translation: [Real; 3],
+    pub rotation: [Real; 3],
+    pub scale: [Real; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl Transform {
+    /// Smallest scale the editors allow along any axis. Zero would flatten
+    /// the solid and negative values turn it inside out, which the booleans
+    /// cannot handle.
+    pub const MIN_SCALE: Real = 0.001;
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The rotation alone, composed `Rz * Ry * Rx` like `CSG::rotate`.
+    pub fn rotation_matrix(&self) -> Rotation3<Real> {
+        let [rx, ry, rz] = self.rotation.map(Real::to_radians);
+        Rotation3::from_euler_angles(rx, ry, rz)
+    }
+
+    /// Set the rotation from a matrix, inverting [`Self::rotation_matrix`].
+    pub fn set_rotation_matrix(&mut self, rotation: &Rotation3<Real>) {
+        let (rx, ry, rz) = rotation.euler_angles();
+        self.rotation = [rx, ry, rz].map(Real::to_degrees);
+    }
+
+    pub fn matrix(&self) -> Matrix4<Real> {
+        Translation3::from(Vector3::from(self.translation)).to_homogeneous()
+            * self.rotation_matrix().to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&Vector3::from(self.scale))
+    }
+
+    /// Refuse values that are not finite numbers and raise the scale to
+    /// [`Self::MIN_SCALE`], as the editors would.
+    pub fn sanitize(&mut self) -> Result<(), String> {
+        let mut values = self
+            .translation
+            .iter()
+            .chain(&self.rotation)
+            .chain(&self.scale);
+        if !values.all(|x| x.is_finite()) {
+            return Err("a transform is not a finite number".into());
+        }
+        self.scale = self.scale.map(|s| s.max(Self::MIN_SCALE));
+        Ok(())
+    }
+
+    pub fn apply(&self, csg: CSG<()>) -> CSG<()> {
+        if self.is_identity() {
+            return csg;
+        }
+        let matrix = self.matrix();
+        // a zero scale flattens the solid away (and cannot be inverted for
+        // the normals)
+        if matrix.determinant().abs() < Real::EPSILON {
+            return CSG::new();
+        }
+        csg.transform(&matrix)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BooleanOp {
+    Union,
+    /// The first child minus all the others.
+    Difference,
+    Intersection,
+    Xor,
+}
+
+impl BooleanOp {
+    pub const ALL: [Self; 4] = [Self::Union, Self::Difference, Self::Intersection, Self::Xor];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Union => "Union",
+            Self::Difference => "Difference",
+            Self::Intersection => "Intersection",
+            Self::Xor => "Xor",
+        }
+    }
+
+    fn apply(self, a: &CSG<()>, b: &CSG<()>) -> CSG<()> {
+        match self {
+            Self::Union => a.union(b),
+            Self::Difference => difference(a, b),
+            Self::Intersection => a.intersection(b),
+            Self::Xor => difference(a, b).union(&difference(b, a)),
+        }
+    }
+}
+
+/// `a` minus `b`.
+///
+/// `CSG::difference` in csgrs 0.18 sets aside the faces of `a` that cannot
+/// reach `b`'s bounding box, to spare them the BSP work, and then forgets to
+/// return them; the result has holes wherever `b` only clips part of `a`.
+/// They are put back here. `CSG::xor` is built on it and has the same flaw.
+pub fn difference(a: &CSG<()>, b: &CSG<()>) -> CSG<()> {
+    let reach = b.bounding_box();
+    let mut polygons = a.difference(b).polygons;
+    polygons.extend(
+        a.polygons
+            .iter()
+            .filter(|p| !p.bounding_box().intersects(&reach))
+            .cloned(),
+    );
+    CSG::from_polygons(&polygons)
+}
+
+/// Triangles read from a mesh file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "StoredMesh", try_from = "StoredMesh")]
+pub struct ImportedMesh {
+    pub file_name: String,
+    pub csg: Arc<CSG<()>>,
+}
+
+impl ImportedMesh {
+    /// Parse binary or ASCII STL. Corners that are not finite numbers are
+    /// refused, as csgrs cannot work with them.
+    pub fn from_stl(file_name: &str, data: &[u8]) -> std::io::Result<Self> {
+        let csg = CSG::from_stl(data, None)?;
+        let finite = csg
+            .polygons
+            .iter()
+            .flat_map(|p| &p.vertices)
+            .all(|v| v.pos.iter().all(|x| x.is_finite()));
+        if !finite {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "a corner is not a finite number",
+            ));
+        }
+        Ok(Self {
+            file_name: file_name.to_owned(),
+            csg: Arc::new(csg),
+        })
+    }
+}
+
+/// An [`ImportedMesh`] as written out: its triangles as base64 binary STL,
+/// so storage never needs the original file.
+#[derive(Serialize, Deserialize)]
+struct StoredMesh {
+    file_name: String,
+    stl: String,
+}
+
+impl From<ImportedMesh> for StoredMesh {
+    fn from(mesh: ImportedMesh) -> Self {
+        Self {
+            stl: BASE64.encode(stl_bytes(&mesh.csg)),
+            file_name: mesh.file_name,
+        }
+    }
+}
+
+impl TryFrom<StoredMesh> for ImportedMesh {
+    type Error = String;
+
+    fn try_from(stored: StoredMesh) -> Result<Self, String> {
+        let bytes = BASE64.decode(&stored.stl).map_err(|e| e.to_string())?;
+        Self::from_stl(&stored.file_name, &bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Binary STL of `csg`, fanning polygons into triangles.
+///
+/// Unlike `CSG::to_stl_binary` this keeps every triangle's corner order, so
+/// a mesh read from STL is written back bit for bit.
+fn stl_bytes(csg: &CSG<()>) -> Vec<u8> {
+    let triangles: Vec<_> = csg
+        .polygons
+        .iter()
+        .flat_map(|p| {
+            let v = &p.vertices;
+            (1..v.len().saturating_sub(1)).map(move |i| [&v[0], &v[i], &v[i + 1]])
+        })
+        .collect();
+    let mut out = vec![0; 80];
+    out.extend((triangles.len() as u32).to_le_bytes());
+    for corners in triangles {
+        // STL holds one normal per facet; the reader gives it to every corner
+        let normal = corners[0].normal;
+        let points = std::iter::once(normal).chain(corners.iter().map(|c| c.pos.coords));
+        for p in points {
+            for x in p.iter() {
+                out.extend((*x as f32).to_le_bytes());
+            }
+        }
+        out.extend(0u16.to_le_bytes());
+    }
+    out
+}
+
+impl PartialEq for ImportedMesh {
+    fn eq(&self, other: &Self) -> bool {
+        self.file_name == other.file_name && Arc::ptr_eq(&self.csg, &other.csg)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Primitive(Primitive),
+    Mesh(ImportedMesh),
+    Boolean(BooleanOp),
+}
+
+/// One node of the construction tree. Leaves are primitives or imported
+/// meshes; boolean nodes combine their children in order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub id: NodeId,
+    pub name: String,
+    pub kind: NodeKind,
+    pub transform: Transform,
+    pub children: Vec<Node>,
+    /// Colour the object is tagged with, shown next to its name. The model
+    /// is still drawn in one colour: booleans do not keep track of which
+    /// object a face came from.
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+    /// Free-form notes, e.g. a part number or material.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Node {
+    /// Evaluate this subtree into a single solid.
+    pub fn evaluate(&self) -> CSG<()> {
+        let csg = match &self.kind {
+            NodeKind::Primitive(primitive) => primitive.build(),
+            NodeKind::Mesh(mesh) => (*mesh.csg).clone(),
+            NodeKind::Boolean(op) => {
+                let mut children = self.children.iter().map(Node::evaluate);
+                match children.next() {
+                    Some(first) => children.fold(first, |acc, c| op.apply(&acc, &c)),
+                    None => CSG::new(),
+                }
+            }
+        };
+        self.transform.apply(csg)
+    }
+
+    /// [`Primitive::sanitize`] and [`Transform::sanitize`] for this
+    /// subtree; the error names the node.
+    fn sanitize(&mut self) -> Result<(), String> {
+        let checked = match &mut self.kind {
+            NodeKind::Primitive(primitive) => primitive.sanitize(),
+            _ => Ok(()),
+        };
+        checked
+            .and_then(|()| self.transform.sanitize())
+            .map_err(|e| format!("{}: {e}", self.name))?;
+        self.children.iter_mut().try_for_each(Node::sanitize)
+    }
+
+    /// Largest id in this subtree.
+    fn max_id(&self) -> NodeId {
+        self.children
+            .iter()
+            .map(Node::max_id)
+            .fold(self.id, NodeId::max)
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        matches!(self.kind, NodeKind::Boolean(_))
+    }
+
+    pub fn find(&self, id: NodeId) -> Option<&Node> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find(id))
+    }
+
+    pub fn find_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|c| c.find_mut(id))
+    }
+
+    /// Product of the transforms of the nodes above `id`, mapping its
+    /// parent's space into world space.
+    pub fn parent_matrix(&self, id: NodeId) -> Option<Matrix4<Real>> {
+        if self.id == id {
+            return Some(Matrix4::identity());
+        }
+        self.children
+            .iter()
+            .find_map(|c| c.parent_matrix(id))
+            .map(|m| self.transform.matrix() * m)
+    }
+
+    /// The node whose `children` contain `id`.
+    pub fn parent_of(&self, id: NodeId) -> Option<&Node> {
+        if self.children.iter().any(|c| c.id == id) {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.parent_of(id))
+    }
+
+    fn parent_of_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        if self.children.iter().any(|c| c.id == id) {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|c| c.parent_of_mut(id))
+    }
+}
+
+/// What one model unit stands for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    /// Plain numbers, as STL files carry no units.
+    #[default]
+    Unitless,
+    Millimetres,
+    Centimetres,
+    Metres,
+    Inches,
+}
+
+impl Units {
+    pub const ALL: [Self; 5] = [
+        Self::Unitless,
+        Self::Millimetres,
+        Self::Centimetres,
+        Self::Metres,
+        Self::Inches,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Unitless => "None",
+            Self::Millimetres => "Millimetres",
+            Self::Centimetres => "Centimetres",
+            Self::Metres => "Metres",
+            Self::Inches => "Inches",
+        }
+    }
+
+    /// Symbol written after a length, empty without units.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Unitless => "",
+            Self::Millimetres => "mm",
+            Self::Centimetres => "cm",
+            Self::Metres => "m",
+            Self::Inches => "in",
+        }
+    }
+
+    /// `value` followed by the symbol raised to `power` (2 for areas, 3 for
+    /// volumes), with `precision` decimals.
+    pub fn format(self, value: Real, power: u8, precision: usize) -> String {
+        let power = match power {
+            2 => "²",
+            3 => "³",
+            _ => "",
+        };
+        match self {
+            Self::Unitless => format!("{value:.precision$}"),
+            _ => format!("{value:.precision$} {}{power}", self.symbol()),
+        }
+    }
+}
+
+/// The construction tree shown in the viewer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub root: Node,
+    next_id: u64,
+    #[serde(default)]
+    pub units: Units,
+}
+
+impl Default for Scene {
+    /// A union holding the icosahedron the viewer has always opened with.
+    fn default() -> Self {
+        Self::with_leaf(NodeKind::Primitive(Primitive::Icosahedron { radius: 2.0 }))
+    }
+}
+
+impl Scene {
+    /// A fresh scene whose root union holds a single `leaf`.
+    pub fn with_leaf(leaf: NodeKind) -> Self {
+        let mut scene = Self {
+            root: Node {
+                id: NodeId(0),
+                name: "Union".into(),
+                kind: NodeKind::Boolean(BooleanOp::Union),
+                transform: Transform::default(),
+                children: Vec::new(),
+                color: None,
+                metadata: BTreeMap::new(),
+            },
+            next_id: 1,
+            units: Units::default(),
+        };
+        let leaf = scene.new_node(leaf);
+        scene.root.children.push(leaf);
+        scene
+    }
+
+    pub fn evaluate(&self) -> CSG<()> {
+        self.root.evaluate()
+    }
+
+    /// Make a scene read from a file or link safe to evaluate and edit: the
+    /// checks of [`Node::sanitize`] on every node, and ids handed out after
+    /// the ones in use.
+    pub fn sanitize(&mut self) -> Result<(), String> {
+        self.root.sanitize()?;
+        self.next_id = self.next_id.max(self.root.max_id().0.saturating_add(1));
+        Ok(())
+    }
+
+    /// A fresh, unattached node with an identity transform.
+    pub fn new_node(&mut self, kind: NodeKind) -> Node {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        let name = match &kind {
+            NodeKind::Primitive(p) => format!("{} {}", p.label(), id.0),
+            NodeKind::Mesh(m) => m.file_name.clone(),
+            NodeKind::Boolean(op) => format!("{} {}", op.label(), id.0),
+        };
+        Node {
+            id,
+            name,
+            kind,
+            transform: Transform::default(),
+            children: Vec::new(),
+            color: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Append `node` to `parent`, or next to it when `parent` is a leaf.
+    pub fn insert(&mut self, parent: NodeId, node: Node) {
+        let target = match self.root.find(parent) {
+            Some(p) if p.is_boolean() => parent,
+            _ => self.root.parent_of(parent).map_or(self.root.id, |p| p.id),
+        };
+        if let Some(target) = self.root.find_mut(target) {
+            target.children.push(node);
+        }
+    }
+
+    /// Detach `id` from the tree. The root cannot be removed.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        let parent = self.root.parent_of_mut(id)?;
+        let i = parent.children.iter().position(|c| c.id == id)?;
+        Some(parent.children.remove(i))
+    }
+
+    /// Move `id` one place among its siblings; `up` moves towards the front.
+    /// Order matters for `Difference`, whose first child is the base.
+    pub fn reorder(&mut self, id: NodeId, up: bool) {
+        let Some(parent) = self.root.parent_of_mut(id) else {
+            return;
+        };
+        let Some(i) = parent.children.iter().position(|c| c.id == id) else {
+            return;
+        };
+        let j = if up { i.checked_sub(1) } else { Some(i + 1) };
+        if let Some(j) = j.filter(|&j| j < parent.children.len()) {
+            parent.children.swap(i, j);
+        }
+    }
+
+    /// Replace the leaf or boolean `id` with a boolean node containing it,
+    /// so a new operand can be combined with it.
+    pub fn wrap(&mut self, id: NodeId, op: BooleanOp) {
+        let mut group = self.new_node(NodeKind::Boolean(op));
+        let Some(node) = self.root.find_mut(id) else {
+            return;
+        };
+        group.children.push(node.clone());
+        *node = group;
+    }
+}
@@ -0,0 +1,512 @@
+use csgrs::csg::CSG;
+use csgrs::float_types::{PI, Real};
+use csgrs::plane::Plane;
+use csgrs::polygon::Polygon;
+use csgrs::vertex::Vertex;
+use eframe::egui::{self, Color32, Shape, Stroke};
+use geo::{Contains, LineString};
+use glam::Vec3;
+use nalgebra::{Matrix4, Point2, Point3, Rotation3, Translation3, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::cpu::Projector;
+use crate::mesh::to_vec3;
+use crate::pick::segment_distance;
+use crate::weld::{Welder, points_on_edges};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::X => "X",
+            Self::Y => "Y",
+            Self::Z => "Z",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A clipping plane across one model axis.
+///
+/// Everything on the `normal` side of the plane is cut away and the opening
+/// is closed with a flat cap, so cavities show as holes in the cap.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    pub enabled: bool,
+    pub axis: Axis,
+    /// Position of the plane along `axis`.
+    pub offset: Real,
+    /// Cut away the negative side instead of the positive one.
+    pub flip: bool,
+}
+
+impl Default for Section {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            axis: Axis::Z,
+            offset: 0.0,
+            flip: false,
+        }
+    }
+}
+
+/// A sectioned model.
+pub struct Cut {
+    /// The kept part of the model, capped.
+    pub csg: CSG<()>,
+    /// Hatching across the cap, in model space.
+    pub hatch: Vec<[Point3<Real>; 2]>,
+}
+
+/// Cut points closer than this, per model size, are the same point.
+const CHAIN_TOLERANCE: Real = 1e-9;
+/// Vertices closer than this to the plane, per model size, lie on it.
+const PLANE_TOLERANCE: Real = 1e-9;
+/// Hatch lines per model size (bounding box diagonal).
+const HATCH_LINES: Real = 60.0;
+const HATCH_COLOR: Color32 = Color32::from_rgb(200, 60, 60);
+const PLANE_COLOR: Color32 = Color32::from_rgb(90, 170, 255);
+/// On-screen length of the drag handle.
+const HANDLE_PX: f32 = 60.0;
+const PICK_PX: f32 = 8.0;
+
+impl Section {
+    /// Unit normal pointing into the removed half.
+    pub fn normal(&self) -> Vector3<Real> {
+        let n = Vector3::ith(self.axis.index(), 1.0);
+        if self.flip { -n } else { n }
+    }
+
+    /// A point on the plane.
+    pub fn origin(&self) -> Point3<Real> {
+        Point3::from(Vector3::ith(self.axis.index(), self.offset))
+    }
+
+    /// Maps model space to a frame where the plane is `z = 0` and the
+    /// removed half is `z > 0`, so clipping only looks at `z` and the cut
+    /// loops and cap are worked out in the xy plane.
+    fn frame(&self) -> Matrix4<Real> {
+        let rotation = Rotation3::rotation_between(&self.normal(), &Vector3::z())
+            .unwrap_or_else(|| Rotation3::from_axis_angle(&Vector3::x_axis(), PI));
+        rotation.to_homogeneous() * Translation3::from(-self.origin().coords).to_homogeneous()
+    }
+
+    /// Clip `csg` at the plane and cap the opening.
+    pub fn apply(&self, csg: &CSG<()>) -> Cut {
+        let frame = self.frame();
+        let Some(back) = frame.try_inverse() else {
+            return Cut {
+                csg: csg.clone(),
+                hatch: Vec::new(),
+            };
+        };
+        let local = csg.transform(&frame);
+        let plane = Plane::from_normal(Vector3::z(), 0.0);
+
+        let mut polygons = Vec::with_capacity(local.polygons.len());
+        for polygon in &local.polygons {
+            // faces lying in the plane and facing the removed half are the
+            // kept part's own top faces
+            let (coplanar_front, _, _, kept) = plane.split_polygon(polygon);
+            polygons.extend(coplanar_front);
+            polygons.extend(kept);
+        }
+
+        let size = csg.bounding_box().extents().norm();
+        let loops = cut_loops(&local.polygons, size);
+        polygons.extend(cap(&loops));
+
+        let hatch = hatch(&loops, size / HATCH_LINES)
+            .into_iter()
+            .map(|[a, b]| [a, b].map(|p| back.transform_point(&Point3::new(p.x, p.y, 0.0))))
+            .collect();
+        Cut {
+            csg: CSG::from_polygons(&polygons).transform(&back),
+            hatch,
+        }
+    }
+
+    /// Outline of the plane across `bounds` and a handle to drag it along
+    /// its axis. Returns whether the offset changed and whether the pointer
+    /// is on the handle.
+    pub fn handle(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        projector: &Projector,
+        bounds: (Point3<Real>, Point3<Real>),
+        shapes: &mut Vec<Shape>,
+    ) -> (bool, bool) {
+        let axis = self.axis.index();
+        let (min, max) = bounds;
+        let margin = (max - min).norm() * 0.05;
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let corner = |a: Real, b: Real| {
+            let mut p = self.origin();
+            p[u] = a;
+            p[v] = b;
+            projector.project(to_vec3(&p.coords)).0
+        };
+        let (u0, u1, v0, v1) = (
+            min[u] - margin,
+            max[u] + margin,
+            min[v] - margin,
+            max[v] + margin,
+        );
+        let outline = vec![
+            corner(u0, v0),
+            corner(u1, v0),
+            corner(u1, v1),
+            corner(u0, v1),
+        ];
+
+        let center = self.origin();
+        let mut center3 = center;
+        center3[u] = (min[u] + max[u]) * 0.5;
+        center3[v] = (min[v] + max[v]) * 0.5;
+        let (start, depth) = projector.project(to_vec3(&center3.coords));
+        let axis_dir = Vec3::AXES[axis];
+        let along = projector.project(to_vec3(&center3.coords) + axis_dir).0 - start;
+        if depth <= 0.0 || along.length() < 1e-3 {
+            return (false, false);
+        }
+        let px_per_unit = along.length();
+        let tip = start + along.normalized() * HANDLE_PX;
+
+        let id = response.id.with("section handle");
+        let hovered = response
+            .hover_pos()
+            .is_some_and(|p| segment_distance(p, start, tip) < PICK_PX);
+        let mut dragging = ui.memory(|m| m.data.get_temp::<bool>(id)).unwrap_or(false);
+        if hovered && response.drag_started_by(egui::PointerButton::Primary) {
+            dragging = true;
+        }
+        if !ui.input(|i| i.pointer.primary_down()) {
+            dragging = false;
+        }
+        ui.memory_mut(|m| m.data.insert_temp(id, dragging));
+
+        let mut changed = false;
+        if dragging {
+            let delta = response.drag_delta();
+            let amount = delta.dot(along.normalized()) / px_per_unit;
+            if amount != 0.0 {
+                self.offset = (self.offset + amount as Real).clamp(min[axis], max[axis]);
+                changed = true;
+            }
+        }
+
+        let color = if hovered || dragging {
+            Color32::YELLOW
+        } else {
+            PLANE_COLOR
+        };
+        shapes.push(Shape::closed_line(outline, Stroke::new(1.5, PLANE_COLOR)));
+        shapes.push(Shape::line_segment([start, tip], Stroke::new(2.5, color)));
+        shapes.push(Shape::circle_filled(tip, 5.0, color));
+        (changed, hovered || dragging)
+    }
+}
+
+/// Hatching drawn over the cap, when the cap faces the eye.
+pub fn paint_hatch(
+    painter: &egui::Painter,
+    projector: &Projector,
+    section: &Section,
+    hatch: &[[Point3<Real>; 2]],
+) {
+    let eye = projector.eye();
+    let to_eye = Vector3::new(eye.x, eye.y, eye.z).cast::<Real>() - section.origin().coords;
+    // seen from the kept side the cap is behind the model
+    if to_eye.dot(&section.normal()) <= 0.0 {
+        return;
+    }
+    let stroke = Stroke::new(1.0, HATCH_COLOR);
+    for [a, b] in hatch {
+        let a = projector.project(to_vec3(&a.coords)).0;
+        let b = projector.project(to_vec3(&b.coords)).0;
+        painter.line_segment([a, b], stroke);
+    }
+}
+
+/// Closed outlines of the section through `polygons` at `z = 0`, walked
+/// with the solid on the left seen from `+z`; `size` scales the tolerances.
+///
+/// Polygons add the segments where they meet the plane (see [`segments`])
+/// and the segments are chained end to start. `CSG::slice` is not used
+/// because on boolean results it can drop loops, leaving the cap wrong.
+fn cut_loops(polygons: &[Polygon<()>], size: Real) -> Vec<Vec<Point2<Real>>> {
+    let plane_tolerance = size * PLANE_TOLERANCE;
+    let mut welder = Welder::new(size * CHAIN_TOLERANCE);
+    let mut raw = Vec::new();
+    for polygon in polygons {
+        for [a, b] in segments(polygon, plane_tolerance) {
+            let (a, b) = (welder.insert(a), welder.insert(b));
+            if a != b {
+                raw.push([a, b]);
+            }
+        }
+    }
+    let points = welder.into_points();
+    // booleans leave T-junctions, so one polygon's segment can run past
+    // another's end; split it there so the pieces chain and cancel
+    let splits = points_on_edges(&points, &raw, size * CHAIN_TOLERANCE);
+
+    let mut next: HashMap<u32, Vec<u32>> = HashMap::new();
+    for [a, b] in raw {
+        let mut chain = vec![a];
+        chain.extend(splits.get(&[a, b]).into_iter().flatten());
+        chain.push(b);
+        for pair in chain.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            // a segment and its reverse cancel: the plane only grazes the
+            // solid there, or a face in the plane already closes it
+            let reverse = next.get(&b).and_then(|to| to.iter().position(|&t| t == a));
+            match (reverse, next.get_mut(&b)) {
+                (Some(i), Some(to)) => {
+                    to.swap_remove(i);
+                }
+                _ => next.entry(a).or_default().push(b),
+            }
+        }
+    }
+
+    let mut loops = Vec::new();
+    let mut starts: Vec<u32> = next.keys().copied().collect();
+    starts.sort_unstable();
+    for start in starts {
+        while let Some(first) = next.get_mut(&start).and_then(Vec::pop) {
+            let mut ring = vec![start];
+            let mut at = first;
+            while at != start {
+                ring.push(at);
+                match next.get_mut(&at).and_then(Vec::pop) {
+                    Some(to) => at = to,
+                    // an open chain means the solid was open here
+                    None => break,
+                }
+            }
+            if at == start && ring.len() >= 3 {
+                let flat = |i: &u32| Point2::new(points[*i as usize].x, points[*i as usize].y);
+                loops.push(ring.iter().map(flat).collect());
+            }
+        }
+    }
+    loops
+}
+
+/// Where a convex `polygon` meets `z = 0`, as segments directed so the kept
+/// solid (`z < 0`) is on their left seen from `+z`:
+/// - a polygon crossing the plane adds the line across it;
+/// - a kept polygon adds its edges lying in the plane;
+/// - a face lying in the plane and facing `+z` already closes the section
+///   there, so it adds its outline reversed, to cancel those segments.
+fn segments(polygon: &Polygon<()>, tolerance: Real) -> Vec<[Point3<Real>; 2]> {
+    let side = |z: Real| {
+        if z > tolerance {
+            1
+        } else if z < -tolerance {
+            -1
+        } else {
+            0
+        }
+    };
+    let vertices = &polygon.vertices;
+    let n = vertices.len();
+    let sides: Vec<i32> = vertices.iter().map(|v| side(v.pos.z)).collect();
+    let flat = |p: &Point3<Real>| Point3::new(p.x, p.y, 0.0);
+    let normal = polygon.plane.normal();
+    // solid on the left of a line along `z × normal`
+    let orient = |a: Point3<Real>, b: Point3<Real>| {
+        if (b - a).dot(&Vector3::z().cross(&normal)) >= 0.0 {
+            [a, b]
+        } else {
+            [b, a]
+        }
+    };
+    let edges = (0..n).map(|i| (i, (i + 1) % n));
+
+    if sides.iter().all(|&s| s == 0) {
+        if normal.z <= 0.0 {
+            return Vec::new();
+        }
+        return edges
+            .map(|(i, j)| [flat(&vertices[j].pos), flat(&vertices[i].pos)])
+            .collect();
+    }
+    if !sides.contains(&1) {
+        return edges
+            .filter(|&(i, j)| sides[i] == 0 && sides[j] == 0)
+            .map(|(i, j)| orient(flat(&vertices[i].pos), flat(&vertices[j].pos)))
+            .collect();
+    }
+    if !sides.contains(&-1) {
+        return Vec::new();
+    }
+    let mut points = Vec::with_capacity(2);
+    for (i, j) in edges {
+        let (p, q) = (&vertices[i].pos, &vertices[j].pos);
+        if sides[i] == 0 {
+            points.push(flat(p));
+        } else if sides[i] * sides[j] < 0 {
+            points.push(flat(&(p + (q - p) * (p.z / (p.z - q.z)))));
+        }
+    }
+    match (points.first(), points.last()) {
+        (Some(&a), Some(&b)) => vec![orient(a, b)],
+        _ => Vec::new(),
+    }
+}
+
+/// Triangulated cap over the cut loops, facing `+z`.
+///
+/// `cut_loops` returns every loop on its own, so nesting is worked out here:
+/// a loop inside an even number of others is solid and those directly inside
+/// it are its holes (cavities).
+fn cap(loops: &[Vec<Point2<Real>>]) -> Vec<Polygon<()>> {
+    let rings: Vec<LineString<Real>> = loops
+        .iter()
+        .map(|l| l.iter().map(|p| (p.x, p.y)).collect())
+        .collect();
+    let polygons: Vec<geo::Polygon<Real>> = rings
+        .iter()
+        .map(|r| geo::Polygon::new(r.clone(), Vec::new()))
+        .collect();
+    let inside =
+        |i: usize, j: usize| i != j && rings[i].0.first().is_some_and(|c| polygons[j].contains(c));
+    let depth: Vec<usize> = (0..rings.len())
+        .map(|i| (0..rings.len()).filter(|&j| inside(i, j)).count())
+        .collect();
+
+    let as_array = |r: &LineString<Real>| r.coords().map(|c| [c.x, c.y]).collect::<Vec<_>>();
+    let mut out = Vec::new();
+    for (i, ring) in rings.iter().enumerate() {
+        if depth[i] % 2 == 1 {
+            continue;
+        }
+        let holes: Vec<Vec<[Real; 2]>> = (0..rings.len())
+            .filter(|&j| depth[j] == depth[i] + 1 && inside(j, i))
+            .map(|j| as_array(&rings[j]))
+            .collect();
+        let hole_refs: Vec<&[[Real; 2]]> = holes.iter().map(Vec::as_slice).collect();
+        for mut triangle in CSG::<()>::tessellate_2d(&as_array(ring), &hole_refs) {
+            let [a, b, c] = triangle;
+            if (b - a).cross(&(c - a)).z < 0.0 {
+                triangle.swap(1, 2);
+            }
+            let vertices = triangle
+                .iter()
+                .map(|p| Vertex::new(*p, Vector3::z()))
+                .collect();
+            out.push(Polygon::new(vertices, None));
+        }
+    }
+    out
+}
+
+/// 45° hatch segments inside the loops (even-odd), `spacing` apart.
+fn hatch(loops: &[Vec<Point2<Real>>], spacing: Real) -> Vec<[Point2<Real>; 2]> {
+    if spacing <= 0.0 {
+        return Vec::new();
+    }
+    // scan along rotated coordinates: s = x + y runs across the hatch lines
+    let s = |p: &Point2<Real>| p.x + p.y;
+    let t = |p: &Point2<Real>| p.x - p.y;
+    let edges: Vec<(&Point2<Real>, &Point2<Real>)> = loops
+        .iter()
+        .flat_map(|l| l.iter().zip(l.iter().cycle().skip(1)))
+        .collect();
+    let (lo, hi) = edges
+        .iter()
+        .fold((Real::MAX, Real::MIN), |(lo, hi), (a, _)| {
+            (lo.min(s(a)), hi.max(s(a)))
+        });
+
+    let mut out = Vec::new();
+    let mut line = (lo / spacing).ceil() * spacing;
+    while line <= hi {
+        let mut crossings: Vec<Real> = edges
+            .iter()
+            .filter(|(a, b)| (s(a) <= line) != (s(b) <= line))
+            .map(|(a, b)| {
+                let f = (line - s(a)) / (s(b) - s(a));
+                t(a) + (t(b) - t(a)) * f
+            })
+            .collect();
+        crossings.sort_by(Real::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            // back from (s, t) to (x, y)
+            let point = |t: Real| Point2::new((line + t) * 0.5, (line - t) * 0.5);
+            out.push([point(pair[0]), point(pair[1])]);
+        }
+        line += spacing;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use csgrs::csg::CSG;
+    use csgrs::float_types::{PI, Real};
+
+    use super::{Axis, Section};
+    use crate::scene::difference;
+    use crate::validate::Report;
+    use crate::weld::WeldedMesh;
+
+    /// Area of the faces lying in the plane `z = height` and facing `+z`.
+    fn cap_area(csg: &CSG<()>, height: Real) -> Real {
+        csg.polygons
+            .iter()
+            .filter(|p| p.plane.normal().normalize().z > 0.999)
+            .filter(|p| p.vertices.iter().all(|v| (v.pos.z - height).abs() < 1e-9))
+            .map(|p| {
+                let v = &p.vertices;
+                (1..v.len() - 1)
+                    .map(|k| {
+                        (v[k].pos - v[0].pos)
+                            .cross(&(v[k + 1].pos - v[0].pos))
+                            .norm()
+                            * 0.5
+                    })
+                    .sum::<Real>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn holed_cube_cap_is_a_ring() {
+        const SEGMENTS: usize = 32;
+        const RADIUS: Real = 0.4;
+        let cube = CSG::cube(2.0, 2.0, 2.0, None);
+        let hole = CSG::cylinder(RADIUS, 4.0, SEGMENTS, None).translate(1.0, 1.0, -1.0);
+        let section = Section {
+            enabled: true,
+            axis: Axis::Z,
+            offset: 0.1,
+            flip: false,
+        };
+        let cut = section.apply(&difference(&cube, &hole)).csg;
+
+        let n = SEGMENTS as Real;
+        let hole_area = 0.5 * n * RADIUS * RADIUS * (2.0 * PI / n).sin();
+        let area = cap_area(&cut, 0.1);
+        assert!((area - (4.0 - hole_area)).abs() < 1e-9, "cap area {area}");
+
+        let report = Report::check(&WeldedMesh::from_csg(&cut, 1e-5));
+        assert!(report.is_clean(), "{}", report.summary());
+    }
+}
@@ -0,0 +1,157 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::Camera;
+use crate::scene::Scene;
+
+/// Prefix of the URL fragment that carries a model, e.g. `#model=…`.
+const KEY: &str = "model=";
+/// Bumped whenever [`Shared`] changes in a way older builds cannot read.
+const VERSION: u32 = 1;
+/// Largest model a link may expand to; guards against decompression bombs.
+const MAX_BYTES: usize = 64 << 20;
+
+/// What a shared link carries: the construction tree and the view of it.
+///
+/// Encoded as RON, deflated and base64url'd into the URL fragment, which
+/// browsers never send to a server.
+#[derive(Serialize, Deserialize)]
+pub struct Shared {
+    version: u32,
+    pub scene: Scene,
+    pub camera: Camera,
+}
+
+impl Shared {
+    pub fn new(scene: Scene, camera: Camera) -> Self {
+        Self {
+            version: VERSION,
+            scene,
+            camera,
+        }
+    }
+
+    /// The URL fragment (without `#`) for this state.
+    pub fn to_fragment(&self) -> Result<String, String> {
+        let text = ron::to_string(self).map_err(|e| e.to_string())?;
+        let packed = miniz_oxide::deflate::compress_to_vec(text.as_bytes(), 9);
+        Ok(format!("{KEY}{}", BASE64.encode(packed)))
+    }
+
+    /// Read a link, or just its fragment. `None` when it does not hold a
+    /// model at all.
+    pub fn from_link(link: &str) -> Option<Result<Self, String>> {
+        let fragment = link.split_once('#').map_or(link, |(_, fragment)| fragment);
+        let payload = fragment.strip_prefix(KEY)?;
+        Some(Self::decode(payload))
+    }
+
+    fn decode(payload: &str) -> Result<Self, String> {
+        let packed = BASE64.decode(payload).map_err(|e| e.to_string())?;
+        let text = miniz_oxide::inflate::decompress_to_vec_with_limit(&packed, MAX_BYTES)
+            .map_err(|e| e.to_string())?;
+        let text = std::str::from_utf8(&text).map_err(|e| e.to_string())?;
+        // read the version on its own first, so a newer link says so
+        // instead of failing on whatever field changed
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } = ron::from_str(text).map_err(|e| e.to_string())?;
+        if version > VERSION {
+            return Err(format!(
+                "it was made by a newer version of the viewer (format {version})"
+            ));
+        }
+        let mut shared: Self = ron::from_str(text).map_err(|e| e.to_string())?;
+        shared.scene.sanitize()?;
+        shared.camera.sanitize()?;
+        Ok(shared)
+    }
+}
+
+/// Address of this page with `fragment` in place of any current one; on the
+/// desktop, where there is no page, just the `#fragment`.
+pub fn link(fragment: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(href) = web_sys::window().and_then(|w| w.location().href().ok()) {
+        let page = href.split_once('#').map_or(href.as_str(), |(page, _)| page);
+        return format!("{page}#{fragment}");
+    }
+    format!("#{fragment}")
+}
+
+/// Fragment of the page URL, if any.
+#[cfg(target_arch = "wasm32")]
+pub fn page_fragment() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    (!hash.is_empty()).then_some(hash)
+}
+
+/// Show `fragment` in the address bar without reloading or adding a history
+/// entry, so the link can be copied from there if the clipboard is refused.
+#[cfg(target_arch = "wasm32")]
+pub fn set_page_fragment(fragment: &str) {
+    if let Some(history) = web_sys::window().and_then(|w| w.history().ok()) {
+        let url = link(fragment);
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
+    }
+}
+
+/// Drop the fragment from the address bar without reloading or adding a
+/// history entry, so a reload keeps later edits instead of the link's model.
+#[cfg(target_arch = "wasm32")]
+pub fn clear_page_fragment() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let (Ok(history), Ok(href)) = (window.history(), window.location().href()) else {
+        return;
+    };
+    let page = href.split_once('#').map_or(href.as_str(), |(page, _)| page);
+    let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(page));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Primitive;
+    use crate::scene::NodeKind;
+    use csgrs::float_types::Real;
+
+    fn round_trip(primitive: Primitive) -> Result<Shared, String> {
+        let scene = Scene::with_leaf(NodeKind::Primitive(primitive));
+        let fragment = Shared::new(scene, Camera::default()).to_fragment()?;
+        Shared::from_link(&fragment).unwrap()
+    }
+
+    #[test]
+    fn rejects_sizes_that_are_not_numbers() {
+        let cube = Primitive::Cube {
+            width: Real::NAN,
+            length: 1.0,
+            height: 1.0,
+        };
+        assert!(round_trip(cube).is_err());
+    }
+
+    #[test]
+    fn clamps_segment_counts() {
+        let sphere = Primitive::Sphere {
+            radius: 1.0,
+            segments: 4_000_000_000,
+            stacks: 0,
+        };
+        let shared = round_trip(sphere).unwrap();
+        let NodeKind::Primitive(sphere) = &shared.scene.root.children[0].kind else {
+            panic!("the leaf is not a primitive");
+        };
+        let expected = Primitive::Sphere {
+            radius: 1.0,
+            segments: 128,
+            stacks: 2,
+        };
+        assert_eq!(*sphere, expected);
+    }
+}
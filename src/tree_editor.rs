@@ -0,0 +1,301 @@
+use csgrs::float_types::Real;
+use eframe::egui;
+use std::ops::RangeInclusive;
+
+use crate::primitives::Primitive;
+use crate::scene::{BooleanOp, Node, NodeId, NodeKind, Scene, Transform, Units};
+
+/// Colour a node gets when it is first tagged.
+const DEFAULT_TAG: [u8; 3] = [230, 160, 60];
+
+/// Structural edits requested while drawing the tree, applied afterwards so
+/// the tree is not mutated while it is being iterated.
+enum Action {
+    Add(NodeKind),
+    Remove(NodeId),
+    Reorder(NodeId, bool),
+    Wrap(NodeId, BooleanOp),
+}
+
+/// Side-panel editor for the construction tree.
+///
+/// Returns `true` when the geometry changed and the scene must be
+/// re-evaluated.
+pub fn tree_editor(ui: &mut egui::Ui, scene: &mut Scene, selected: &mut Option<NodeId>) -> bool {
+    let mut actions = Vec::new();
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.menu_button("Add primitive", |ui| {
+            for shape in Primitive::library() {
+                if ui.button(shape.label()).clicked() {
+                    actions.push(Action::Add(NodeKind::Primitive(shape)));
+                    ui.close_menu();
+                }
+            }
+        });
+        ui.menu_button("Add boolean", |ui| {
+            for op in BooleanOp::ALL {
+                if ui.button(op.label()).clicked() {
+                    actions.push(Action::Add(NodeKind::Boolean(op)));
+                    ui.close_menu();
+                }
+            }
+        });
+    });
+    egui::ComboBox::from_label("Units")
+        .selected_text(scene.units.label())
+        .show_ui(ui, |ui| {
+            for units in Units::ALL {
+                ui.selectable_value(&mut scene.units, units, units.label());
+            }
+        });
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .id_source("tree")
+        .max_height(ui.available_height() * 0.5)
+        .show(ui, |ui| node_row(ui, &scene.root, selected));
+    ui.separator();
+
+    let root = scene.root.id;
+    if let Some(node) = selected.and_then(|id| scene.root.find_mut(id)) {
+        let id = node.id;
+        ui.horizontal(|ui| {
+            let not_root = id != root;
+            if ui.add_enabled(not_root, egui::Button::new("⬆")).clicked() {
+                actions.push(Action::Reorder(id, true));
+            }
+            if ui.add_enabled(not_root, egui::Button::new("⬇")).clicked() {
+                actions.push(Action::Reorder(id, false));
+            }
+            ui.menu_button("Wrap in", |ui| {
+                for op in BooleanOp::ALL {
+                    if ui.button(op.label()).clicked() {
+                        actions.push(Action::Wrap(id, op));
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui
+                .add_enabled(not_root, egui::Button::new("🗑 Delete"))
+                .clicked()
+            {
+                actions.push(Action::Remove(id));
+            }
+        });
+        changed |= node_properties(ui, node);
+    } else {
+        ui.weak("Select a node to edit it.");
+    }
+
+    for action in actions {
+        changed = true;
+        match action {
+            Action::Add(kind) => {
+                let node = scene.new_node(kind);
+                let parent = selected.unwrap_or(root);
+                *selected = Some(node.id);
+                scene.insert(parent, node);
+            }
+            Action::Remove(id) => {
+                scene.remove(id);
+                *selected = None;
+            }
+            Action::Reorder(id, up) => scene.reorder(id, up),
+            Action::Wrap(id, op) => scene.wrap(id, op),
+        }
+    }
+    changed
+}
+
+fn node_row(ui: &mut egui::Ui, node: &Node, selected: &mut Option<NodeId>) {
+    let is_selected = *selected == Some(node.id);
+    let label = match &node.kind {
+        NodeKind::Primitive(_) | NodeKind::Mesh(_) => node.name.clone(),
+        NodeKind::Boolean(op) => format!("{} ({})", node.name, op.label()),
+    };
+
+    if node.is_boolean() {
+        let id = ui.make_persistent_id(("tree-node", node.id));
+        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+            .show_header(ui, |ui| {
+                swatch(ui, node.color);
+                if ui.selectable_label(is_selected, label).clicked() {
+                    *selected = Some(node.id);
+                }
+            })
+            .body(|ui| {
+                for child in &node.children {
+                    node_row(ui, child, selected);
+                }
+            });
+    } else {
+        ui.horizontal(|ui| {
+            swatch(ui, node.color);
+            if ui.selectable_label(is_selected, label).clicked() {
+                *selected = Some(node.id);
+            }
+        });
+    }
+}
+
+/// Square of the node's tag colour, or a blank of the same size.
+fn swatch(ui: &mut egui::Ui, color: Option<[u8; 3]>) {
+    let size = egui::Vec2::splat(ui.text_style_height(&egui::TextStyle::Body) * 0.7);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    if let Some([r, g, b]) = color {
+        ui.painter()
+            .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+    }
+}
+
+/// Name, operation or parameters, and transform of one node.
+fn node_properties(ui: &mut egui::Ui, node: &mut Node) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.text_edit_singleline(&mut node.name);
+    });
+    ui.horizontal(|ui| {
+        let mut tagged = node.color.is_some();
+        if ui.checkbox(&mut tagged, "Colour").changed() {
+            node.color = tagged.then_some(DEFAULT_TAG);
+        }
+        if let Some(color) = &mut node.color {
+            ui.color_edit_button_srgb(color);
+        }
+    });
+    metadata_ui(ui, node);
+
+    match &mut node.kind {
+        NodeKind::Boolean(op) => {
+            egui::ComboBox::from_label("Operation")
+                .selected_text(op.label())
+                .show_ui(ui, |ui| {
+                    for candidate in BooleanOp::ALL {
+                        changed |= ui
+                            .selectable_value(op, candidate, candidate.label())
+                            .changed();
+                    }
+                });
+        }
+        NodeKind::Primitive(primitive) => {
+            egui::ComboBox::from_label("Shape")
+                .selected_text(primitive.label())
+                .show_ui(ui, |ui| {
+                    for shape in Primitive::library() {
+                        let same =
+                            std::mem::discriminant(&shape) == std::mem::discriminant(primitive);
+                        if ui.selectable_label(same, shape.label()).clicked() && !same {
+                            *primitive = shape;
+                            changed = true;
+                        }
+                    }
+                });
+            changed |= primitive.ui(ui);
+        }
+        NodeKind::Mesh(mesh) => {
+            ui.label(format!(
+                "Imported from {} ({} polygons)",
+                mesh.file_name,
+                mesh.csg.polygons.len()
+            ));
+        }
+    }
+
+    ui.separator();
+    changed |= transform_ui(ui, &mut node.transform);
+    changed
+}
+
+/// Key/value notes on a node: values are edited in place, keys are added
+/// and removed.
+fn metadata_ui(ui: &mut egui::Ui, node: &mut Node) {
+    egui::CollapsingHeader::new(format!("Metadata ({})", node.metadata.len()))
+        .id_source(("metadata", node.id))
+        .show(ui, |ui| {
+            let mut removed = None;
+            egui::Grid::new("metadata").num_columns(3).show(ui, |ui| {
+                for (key, value) in &mut node.metadata {
+                    ui.label(key);
+                    ui.text_edit_singleline(value);
+                    if ui.small_button("🗑").clicked() {
+                        removed = Some(key.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(key) = removed {
+                node.metadata.remove(&key);
+            }
+
+            // the key being typed lives in egui memory until it is added
+            let id = ui.id().with(("new metadata key", node.id));
+            let mut key: String = ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut key)
+                        .hint_text("Key")
+                        .desired_width(80.0),
+                );
+                let key_trimmed = key.trim();
+                let can_add = !key_trimmed.is_empty() && !node.metadata.contains_key(key_trimmed);
+                if ui.add_enabled(can_add, egui::Button::new("Add")).clicked() {
+                    node.metadata.insert(key_trimmed.to_owned(), String::new());
+                    key.clear();
+                }
+            });
+            ui.data_mut(|d| d.insert_temp(id, key));
+        });
+}
+
+fn transform_ui(ui: &mut egui::Ui, transform: &mut Transform) -> bool {
+    let mut changed = false;
+    egui::Grid::new("transform").num_columns(4).show(ui, |ui| {
+        let any = Real::MIN..=Real::MAX;
+        changed |= vec3_row(
+            ui,
+            "Move",
+            &mut transform.translation,
+            0.05,
+            "",
+            any.clone(),
+        );
+        changed |= vec3_row(ui, "Rotate", &mut transform.rotation, 1.0, "°", any);
+        let positive = Transform::MIN_SCALE..=Real::MAX;
+        changed |= vec3_row(ui, "Scale", &mut transform.scale, 0.01, "", positive);
+    });
+    if ui
+        .add_enabled(
+            !transform.is_identity(),
+            egui::Button::new("Reset transform"),
+        )
+        .clicked()
+    {
+        *transform = Transform::default();
+        changed = true;
+    }
+    changed
+}
+
+fn vec3_row(
+    ui: &mut egui::Ui,
+    label: &str,
+    v: &mut [Real; 3],
+    speed: f64,
+    suffix: &str,
+    range: RangeInclusive<Real>,
+) -> bool {
+    ui.label(label);
+    let mut changed = false;
+    for value in v.iter_mut() {
+        let drag = egui::DragValue::new(value)
+            .speed(speed)
+            .suffix(suffix)
+            .clamp_range(range.clone());
+        changed |= ui.add(drag).changed();
+    }
+    ui.end_row();
+    changed
+}
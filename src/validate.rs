@@ -0,0 +1,173 @@
+use csgrs::float_types::Real;
+use eframe::egui::{self, Color32, Stroke};
+use std::collections::BTreeMap;
+
+use crate::cpu::Projector;
+use crate::mesh::to_vec3;
+use crate::weld::WeldedMesh;
+
+/// Open and non-manifold edges.
+const EDGE_COLOR: Color32 = Color32::from_rgb(235, 40, 40);
+/// Edges whose two faces disagree on which side is outside.
+const WINDING_COLOR: Color32 = Color32::from_rgb(255, 150, 0);
+/// Polygons that collapse to a line or point.
+const DEGENERATE_COLOR: Color32 = Color32::from_rgb(255, 0, 200);
+
+/// Polygons thinner than this (area per unit perimeter) count as degenerate.
+const DEGENERATE_THICKNESS: Real = 1e-9;
+
+/// Everything that keeps the welded model from being a closed, consistently
+/// oriented solid.
+#[derive(Default)]
+pub struct Report {
+    /// Edges used by a single polygon: holes in the surface.
+    pub boundary: Vec<[u32; 2]>,
+    /// Edges shared by more than two polygons.
+    pub non_manifold: Vec<[u32; 2]>,
+    /// Manifold edges walked in the same direction by both polygons.
+    pub flipped: Vec<[u32; 2]>,
+    /// Polygons with fewer than three distinct corners or no area.
+    pub degenerate: Vec<usize>,
+}
+
+impl Report {
+    pub fn check(welded: &WeldedMesh) -> Self {
+        let mut report = Self::default();
+
+        // edge -> whether each polygon using it walks it from low to high
+        let mut walks: BTreeMap<[u32; 2], Vec<bool>> = BTreeMap::new();
+        for (p, ring) in welded.polygons.iter().enumerate() {
+            if ring.len() < 3 || thickness(welded, p) < DEGENERATE_THICKNESS {
+                report.degenerate.push(p);
+            }
+            for (&a, &b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                if a != b {
+                    walks.entry([a.min(b), a.max(b)]).or_default().push(a < b);
+                }
+            }
+        }
+
+        for (edge, walk) in walks {
+            match walk[..] {
+                [_] => report.boundary.push(edge),
+                [first, second] if first == second => report.flipped.push(edge),
+                [_, _] => {}
+                _ => report.non_manifold.push(edge),
+            }
+        }
+        report
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.boundary.is_empty()
+            && self.non_manifold.is_empty()
+            && self.flipped.is_empty()
+            && self.degenerate.is_empty()
+    }
+
+    /// One line for the status bar.
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return "Watertight, consistently oriented".to_owned();
+        }
+        let counts = [
+            (self.boundary.len(), "open edges"),
+            (self.non_manifold.len(), "non-manifold edges"),
+            (self.flipped.len(), "flipped edges"),
+            (self.degenerate.len(), "degenerate polygons"),
+        ];
+        counts
+            .iter()
+            .filter(|(n, _)| *n > 0)
+            .map(|(n, what)| format!("{n} {what}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Draw the problems over the model, visible or not, so nothing can hide
+    /// behind a face.
+    pub fn paint(&self, painter: &egui::Painter, projector: &Projector, welded: &WeldedMesh) {
+        let screen = |i: u32| {
+            projector
+                .project(to_vec3(&welded.vertices[i as usize].coords))
+                .0
+        };
+        let edges = [
+            (&self.boundary, EDGE_COLOR),
+            (&self.non_manifold, EDGE_COLOR),
+            (&self.flipped, WINDING_COLOR),
+        ];
+        for (list, color) in edges {
+            let stroke = Stroke::new(2.5, color);
+            for &[a, b] in list {
+                painter.line_segment([screen(a), screen(b)], stroke);
+            }
+        }
+        for &p in &self.degenerate {
+            let ring = &welded.polygons[p];
+            let Some(&first) = ring.first() else {
+                continue;
+            };
+            let points: Vec<_> = ring.iter().map(|&i| screen(i)).collect();
+            painter.add(egui::Shape::closed_line(
+                points,
+                Stroke::new(2.0, DEGENERATE_COLOR),
+            ));
+            painter.circle_filled(screen(first), 3.5, DEGENERATE_COLOR);
+        }
+    }
+}
+
+/// Twice the area over the perimeter: zero for polygons folded onto a line.
+fn thickness(welded: &WeldedMesh, p: usize) -> Real {
+    let ring = &welded.polygons[p];
+    let corner = |i: &u32| welded.vertices[*i as usize];
+    let perimeter: Real = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| (corner(b) - corner(a)).norm())
+        .sum();
+    if perimeter > 0.0 {
+        2.0 * welded.polygon_area(p) / perimeter
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use csgrs::csg::CSG;
+
+    use super::Report;
+    use crate::scene::difference;
+    use crate::weld::WeldedMesh;
+
+    fn report(csg: &CSG<()>) -> Report {
+        Report::check(&WeldedMesh::from_csg(csg, 1e-5))
+    }
+
+    fn cube(offset: f64) -> CSG<()> {
+        CSG::cube(2.0, 2.0, 2.0, None).translate(offset, offset * 0.5, offset * 0.25)
+    }
+
+    #[test]
+    fn cube_is_watertight() {
+        assert!(report(&cube(0.0)).is_clean());
+    }
+
+    #[test]
+    fn union_is_watertight() {
+        let r = report(&cube(0.0).union(&cube(1.0)));
+        assert!(r.is_clean(), "{}", r.summary());
+    }
+
+    #[test]
+    fn difference_is_watertight() {
+        let r = report(&difference(&cube(0.0), &cube(1.0)));
+        assert!(r.is_clean(), "{}", r.summary());
+
+        let hole = CSG::cylinder(0.4, 4.0, 32, None).translate(1.0, 1.0, -1.0);
+        let r = report(&difference(&cube(0.0), &hole));
+        assert!(r.is_clean(), "{}", r.summary());
+    }
+}
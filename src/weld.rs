@@ -0,0 +1,249 @@
+use csgrs::csg::CSG;
+use csgrs::float_types::Real;
+use glam::Vec3;
+use nalgebra::{Point3, Vector3};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::mesh::to_vec3;
+
+/// Smallest tolerance accepted; keeps the hash grid from degenerating.
+pub const MIN_TOLERANCE: Real = 1e-12;
+
+type CellKey = (i64, i64, i64);
+
+/// Merges points that lie within `tolerance` of an already inserted point.
+///
+/// Points are bucketed in a hash grid whose cells are `tolerance` wide, and
+/// every lookup scans the 27 surrounding cells, so two points closer than the
+/// tolerance always merge even when they straddle a cell boundary.
+pub struct Welder {
+    tolerance: Real,
+    cells: HashMap<CellKey, Vec<u32>>,
+    points: Vec<Point3<Real>>,
+}
+
+impl Welder {
+    pub fn new(tolerance: Real) -> Self {
+        Self {
+            tolerance: tolerance.max(MIN_TOLERANCE),
+            cells: HashMap::new(),
+            points: Vec::new(),
+        }
+    }
+
+    fn cell(&self, p: &Point3<Real>) -> CellKey {
+        let c = |x: Real| (x / self.tolerance).floor() as i64;
+        (c(p.x), c(p.y), c(p.z))
+    }
+
+    /// Index of the welded point for `p`, inserting it if nothing is close.
+    pub fn insert(&mut self, p: Point3<Real>) -> u32 {
+        let (cx, cy, cz) = self.cell(&p);
+        let tol2 = self.tolerance * self.tolerance;
+        let mut best: Option<(u32, Real)> = None;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(bucket) = self.cells.get(&(cx + dx, cy + dy, cz + dz)) else {
+                        continue;
+                    };
+                    for &i in bucket {
+                        let d2 = (self.points[i as usize] - p).norm_squared();
+                        if d2 <= tol2 && best.is_none_or(|(_, b)| d2 < b) {
+                            best = Some((i, d2));
+                        }
+                    }
+                }
+            }
+        }
+        if let Some((i, _)) = best {
+            return i;
+        }
+
+        let i = self.points.len() as u32;
+        self.points.push(p);
+        self.cells.entry((cx, cy, cz)).or_default().push(i);
+        i
+    }
+
+    pub fn into_points(self) -> Vec<Point3<Real>> {
+        self.points
+    }
+}
+
+/// Indexed view of a `CSG` after welding coincident vertices.
+#[derive(Clone, Debug, Default)]
+pub struct WeldedMesh {
+    pub vertices: Vec<Point3<Real>>,
+    /// Each source polygon as a loop of indices into `vertices`, with
+    /// consecutive duplicates collapsed. Loops shorter than three are kept so
+    /// that analysis can report them.
+    pub polygons: Vec<Vec<u32>>,
+    /// Plane normal of each polygon.
+    pub normals: Vec<Vector3<Real>>,
+}
+
+impl WeldedMesh {
+    pub fn from_csg<S: Clone>(csg: &CSG<S>, tolerance: Real) -> Self {
+        let mut welder = Welder::new(tolerance);
+        let mut polygons = Vec::with_capacity(csg.polygons.len());
+        let mut normals = Vec::with_capacity(csg.polygons.len());
+        for poly in &csg.polygons {
+            let mut ring: Vec<u32> = Vec::with_capacity(poly.vertices.len());
+            for v in &poly.vertices {
+                let i = welder.insert(v.pos);
+                if ring.last() != Some(&i) {
+                    ring.push(i);
+                }
+            }
+            while ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            polygons.push(ring);
+            normals.push(poly.plane.normal().normalize());
+        }
+        let mut mesh = Self {
+            vertices: welder.into_points(),
+            polygons,
+            normals,
+        };
+        mesh.split_t_junctions(tolerance.max(MIN_TOLERANCE));
+        mesh
+    }
+
+    /// Area of polygon `p`, assuming it is planar.
+    pub fn polygon_area(&self, p: usize) -> Real {
+        let ring = &self.polygons[p];
+        let corner = |i: &u32| self.vertices[*i as usize].coords;
+        ring.iter()
+            .zip(ring.iter().cycle().skip(1))
+            .map(|(a, b)| corner(a).cross(&corner(b)))
+            .sum::<Vector3<Real>>()
+            .norm()
+            * 0.5
+    }
+
+    /// Insert into each polygon's edges the vertices lying on them.
+    ///
+    /// BSP booleans split a face on one side of an edge without splitting
+    /// its neighbour, so a shared edge shows up as one long edge against
+    /// several short ones and would count as open. Only vertices ending
+    /// other open edges can be such junctions, which keeps this cheap on
+    /// clean meshes.
+    fn split_t_junctions(&mut self, tolerance: Real) {
+        let open: Vec<[u32; 2]> = self
+            .edges()
+            .into_iter()
+            .filter(|(_, polys)| polys.len() == 1)
+            .map(|(edge, _)| edge)
+            .collect();
+        if open.is_empty() {
+            return;
+        }
+
+        let splits = points_on_edges(&self.vertices, &open, tolerance);
+        if splits.is_empty() {
+            return;
+        }
+
+        for ring in &mut self.polygons {
+            let mut split = Vec::with_capacity(ring.len());
+            for (&a, &b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                split.push(a);
+                if let Some(between) = splits.get(&[a.min(b), a.max(b)]) {
+                    // `between` runs from the lower index to the higher
+                    if a < b {
+                        split.extend(between);
+                    } else {
+                        split.extend(between.iter().rev());
+                    }
+                }
+            }
+            *ring = split;
+        }
+    }
+
+    /// Undirected edges (lower index first) mapped to the polygons using them.
+    pub fn edges(&self) -> BTreeMap<[u32; 2], Vec<usize>> {
+        let mut edges: BTreeMap<[u32; 2], Vec<usize>> = BTreeMap::new();
+        for (p, ring) in self.polygons.iter().enumerate() {
+            for (&a, &b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                if a != b {
+                    edges.entry([a.min(b), a.max(b)]).or_default().push(p);
+                }
+            }
+        }
+        edges
+    }
+
+    /// Vertex positions narrowed for the renderer.
+    pub fn vertices_f32(&self) -> Vec<Vec3> {
+        self.vertices.iter().map(|p| to_vec3(&p.coords)).collect()
+    }
+}
+
+/// Which of the `edges`' own endpoints lie inside each edge, within
+/// `tolerance` of it; ordered from the edge's first end to its second.
+/// Edges with none are left out.
+pub fn points_on_edges(
+    points: &[Point3<Real>],
+    edges: &[[u32; 2]],
+    tolerance: Real,
+) -> HashMap<[u32; 2], Vec<u32>> {
+    if edges.is_empty() {
+        return HashMap::new();
+    }
+    // endpoints of the edges, bucketed in cells about one edge long
+    let point = |i: u32| points[i as usize];
+    let length = |[a, b]: [u32; 2]| (point(b) - point(a)).norm();
+    let mean = edges.iter().map(|&e| length(e)).sum::<Real>() / edges.len() as Real;
+    let cell_size = mean.max(tolerance);
+    let cell = |p: &Point3<Real>| {
+        let c = |x: Real| (x / cell_size).floor() as i64;
+        (c(p.x), c(p.y), c(p.z))
+    };
+    let mut cells: HashMap<CellKey, Vec<u32>> = HashMap::new();
+    let ends: HashSet<u32> = edges.iter().flatten().copied().collect();
+    for &v in &ends {
+        cells.entry(cell(&point(v))).or_default().push(v);
+    }
+
+    let mut splits: HashMap<[u32; 2], Vec<u32>> = HashMap::new();
+    for &[a, b] in edges {
+        let (pa, pb) = (point(a), point(b));
+        let d = pb - pa;
+        let len2 = d.norm_squared();
+        if len2 == 0.0 {
+            continue;
+        }
+        // walk the cells along the edge, with their neighbours
+        let steps = (d.norm() / cell_size).ceil() as usize + 1;
+        let mut near = HashSet::new();
+        for k in 0..=steps {
+            let (cx, cy, cz) = cell(&(pa + d * (k as Real / steps as Real)));
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        near.insert((cx + dx, cy + dy, cz + dz));
+                    }
+                }
+            }
+        }
+        let mut on_edge: Vec<(Real, u32)> = near
+            .iter()
+            .filter_map(|c| cells.get(c))
+            .flatten()
+            .filter(|&&v| v != a && v != b)
+            .filter_map(|&v| {
+                let t = (point(v) - pa).dot(&d) / len2;
+                let off = (pa + d * t - point(v)).norm();
+                (t > 0.0 && t < 1.0 && off <= tolerance).then_some((t, v))
+            })
+            .collect();
+        if !on_edge.is_empty() {
+            on_edge.sort_by(|x, y| x.0.total_cmp(&y.0));
+            splits.insert([a, b], on_edge.into_iter().map(|(_, v)| v).collect());
+        }
+    }
+    splits
+}